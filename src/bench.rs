@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, error::Error, fs, time::Duration};
+
+use crate::{
+    parse::{self, ParseError},
+    runner, util,
+};
+
+static STAGES: [&str; 3] = ["parse", "part1", "part2"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Options {
+    // untimed runs before measuring, to warm the caches
+    pub warmup: usize,
+    pub runs: usize,
+    // where to write the medians of this run
+    pub save: Option<String>,
+    // medians from an earlier --save to compare against
+    pub baseline: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            warmup: 1,
+            runs: 10,
+            save: None,
+            baseline: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        Self {
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+// the median time of each stage in nanoseconds, keyed by e.g. "day16/part2"
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Baseline(BTreeMap<String, u128>);
+
+fn key(day: u8, stage: &str) -> String {
+    format!("day{}/{}", day, stage)
+}
+
+impl Baseline {
+    // reads the JSON written by `to_json`, one entry per line, e.g.
+    // {
+    //   "day1/parse": 52000,
+    //   "day1/part1": 1200
+    // }
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        let mut times = BTreeMap::new();
+        for (n, line) in util::numbered_lines(s) {
+            let entry = line.trim();
+            if entry.is_empty() || entry == "{" || entry == "}" {
+                continue;
+            }
+
+            let entry = entry.strip_suffix(',').unwrap_or(entry);
+            let (name, time) = entry
+                .split_once(':')
+                .ok_or_else(|| ParseError::missing(line, "expected `\"name\": time`").at_line(n))?;
+            let name = name.trim();
+            let key = name
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .filter(|_| name.len() >= 2)
+                .ok_or_else(|| ParseError::new(line, name, "expected a quoted name").at_line(n))?;
+            let time = parse::number(line, time.trim()).map_err(|e| e.at_line(n))?;
+            times.insert(key.to_owned(), time);
+        }
+        Ok(Self(times))
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<_> = self
+            .0
+            .iter()
+            .map(|(key, time)| format!("  {:?}: {}", key, time))
+            .collect();
+        format!("{{\n{}\n}}\n", entries.join(",\n"))
+    }
+
+    fn get(&self, day: u8, stage: &str) -> Option<u128> {
+        self.0.get(&key(day, stage)).copied()
+    }
+}
+
+fn fmt_duration(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.1}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.1}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", nanos as f64 / 1e9)
+    }
+}
+
+// the change in the median since the baseline, e.g. +12.5%
+fn fmt_change(median: Duration, baseline: Option<u128>) -> String {
+    match baseline {
+        Some(0) | None => "-".to_owned(),
+        Some(base) => {
+            let change = (median.as_nanos() as f64 - base as f64) / base as f64 * 100.0;
+            format!("{:+.1}%", change)
+        }
+    }
+}
+
+// min, median and max of each stage of a day, after the warm up runs
+pub fn measure(
+    day: &runner::Day,
+    input: &str,
+    options: &Options,
+) -> Result<Vec<Stats>, Box<dyn Error>> {
+    for _ in 0..options.warmup {
+        day.time(input)?;
+    }
+
+    let mut samples = vec![Vec::new(); day.parts as usize + 1];
+    for _ in 0..options.runs.max(1) {
+        for (stage, time) in day.time(input)?.into_iter().enumerate() {
+            samples[stage].push(time);
+        }
+    }
+
+    Ok(samples.into_iter().map(Stats::new).collect())
+}
+
+// times the days (all of them when `only` is None) on the inputs in `dir`
+pub fn bench(dir: &str, only: Option<u8>, options: &Options) -> Result<(), Box<dyn Error>> {
+    let baseline = match &options.baseline {
+        Some(path) => {
+            let s = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            Some(Baseline::parse(&s).map_err(|e| format!("{}: {}", path, e))?)
+        }
+        None => None,
+    };
+
+    println!(
+        "{:>3}  {:<6} {:>10} {:>10} {:>10} {:>10}",
+        "day", "stage", "min", "median", "max", "baseline"
+    );
+
+    let mut medians = Baseline::default();
+    for day in runner::DAYS
+        .iter()
+        .filter(|d| only.is_none_or(|o| o == d.day))
+    {
+        let input = match fs::read_to_string(runner::input_path(dir, day.day)) {
+            Ok(input) => input,
+            Err(_) => {
+                println!("{:>3}  no input", day.day);
+                continue;
+            }
+        };
+
+        let stats = match measure(day, &input, options) {
+            Ok(stats) => stats,
+            Err(e) => {
+                println!("{:>3}  error: {}", day.day, e);
+                continue;
+            }
+        };
+
+        for (stage, s) in STAGES.iter().zip(stats) {
+            let base = baseline.as_ref().and_then(|b| b.get(day.day, stage));
+            println!(
+                "{:>3}  {:<6} {:>10} {:>10} {:>10} {:>10}",
+                day.day,
+                stage,
+                fmt_duration(s.min),
+                fmt_duration(s.median),
+                fmt_duration(s.max),
+                fmt_change(s.median, base)
+            );
+            medians.0.insert(key(day.day, stage), s.median.as_nanos());
+        }
+    }
+
+    if let Some(path) = &options.save {
+        fs::write(path, medians.to_json()).map_err(|e| format!("{}: {}", path, e))?;
+        println!("\nsaved the medians to {}", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::new(vec![ms(5), ms(1), ms(9), ms(3), ms(4)]);
+        assert_eq!(
+            stats,
+            Stats {
+                min: ms(1),
+                median: ms(4),
+                max: ms(9)
+            }
+        );
+    }
+
+    #[test]
+    fn test_baseline() {
+        let mut baseline = Baseline::default();
+        baseline.0.insert(key(1, "parse"), 52000);
+        baseline.0.insert(key(16, "part2"), 1500000000);
+
+        let json = baseline.to_json();
+        assert_eq!(
+            json,
+            "{\n  \"day1/parse\": 52000,\n  \"day16/part2\": 1500000000\n}\n"
+        );
+        assert_eq!(Baseline::parse(&json), Ok(baseline));
+
+        let err = Baseline::parse("{\n  \"day1/parse\": fast\n}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 17: expected a number, found \"fast\""
+        );
+    }
+
+    #[test]
+    fn test_fmt() {
+        assert_eq!(fmt_duration(Duration::from_nanos(1500)), "1.5µs");
+        assert_eq!(fmt_duration(Duration::from_millis(2500)), "2.50s");
+        assert_eq!(fmt_change(Duration::from_nanos(110), Some(100)), "+10.0%");
+        assert_eq!(fmt_change(Duration::from_nanos(110), None), "-");
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+// an unsigned integer of any size, as base 2^32 digits with the least
+// significant first and no leading zeros
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn trim(mut self) -> Self {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+        self
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let mut digits = Vec::with_capacity(self.digits.len().max(other.digits.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.digits.len().max(other.digits.len()) {
+            let a = *self.digits.get(i).unwrap_or(&0) as u64;
+            let b = *other.digits.get(i).unwrap_or(&0) as u64;
+            let sum = a + b + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        BigUint { digits }.trim()
+    }
+
+    // None if other is bigger
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if other.digits.len() > self.digits.len() {
+            return None;
+        }
+        let mut digits = Vec::with_capacity(self.digits.len());
+        let mut borrow = 0;
+        for (i, &a) in self.digits.iter().enumerate() {
+            let b = *other.digits.get(i).unwrap_or(&0) as i64;
+            let mut diff = a as i64 - b - borrow;
+            borrow = 0;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            }
+            digits.push(diff as u32);
+        }
+        (borrow == 0).then(|| BigUint { digits }.trim())
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        BigUint { digits }.trim()
+    }
+
+    // the quotient and remainder
+    pub fn div_rem(&self, divisor: u32) -> (BigUint, u32) {
+        assert!(divisor != 0, "division by zero");
+        let mut digits = vec![0; self.digits.len()];
+        let mut rem = 0u64;
+        for (i, &d) in self.digits.iter().enumerate().rev() {
+            let n = (rem << 32) | d as u64;
+            digits[i] = (n / divisor as u64) as u32;
+            rem = n % divisor as u64;
+        }
+        (BigUint { digits }.trim(), rem as u32)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint {
+            digits: vec![n as u32, (n >> 32) as u32],
+        }
+        .trim()
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // nine decimal digits at a time, most significant last
+        let mut chunks = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (q, r) = n.div_rem(1_000_000_000);
+            chunks.push(r);
+            n = q;
+        }
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                for chunk in rest.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(12345);
+        assert_eq!(a.add(&b).to_string(), "18446744073709563960");
+        assert_eq!(
+            a.mul(&a).to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(a.mul(&a).checked_sub(&a.mul(&a)), Some(BigUint::default()));
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(a.add(&b).checked_sub(&a), Some(b.clone()));
+
+        let (q, r) = a.mul(&b).add(&BigUint::from(7)).div_rem(12345);
+        assert_eq!((q, r), (a, 7));
+        assert_eq!(BigUint::default().to_string(), "0");
+        assert_eq!(BigUint::from(1_000_000_000).to_string(), "1000000000");
+    }
+}
@@ -0,0 +1,336 @@
+use std::{error::Error, fmt::Display, io};
+
+use crate::{bench, runner, util, verify};
+
+pub static USAGE: &str =
+    "usage: aoc2022 run --day <1-25> [--part <1|2>] [--input <path|->] [--threads <n>] [--trace]
+       aoc2022 verify [--day <1-25>] [--inputs <dir>] [--answers <path>] [--threads <n>]
+       aoc2022 bench [--day <1-25>] [--inputs <dir>] [--warmup <n>] [--runs <n>]
+                     [--save <path>] [--baseline <path>] [--threads <n>]
+
+  --day      the puzzle to run, verify and bench do every day when omitted
+  --part     run a single part, both parts are run when omitted
+  --input    path to the puzzle input, `-` reads stdin (default: ../day<N>.txt)
+  --inputs   directory holding day<N>.txt inputs (default: ..)
+  --answers  expected answers, [dayN] sections of partN = answer (default: ../answers.toml)
+  --warmup   untimed runs before timing each day (default: 1)
+  --runs     timed runs of each day (default: 10)
+  --save     write the median times as a JSON baseline
+  --baseline compare the median times with a saved baseline
+  --threads  threads for the days that split up their work (default: one per core)
+  --trace    print the steps of a run before the answers, for the days that have it";
+
+static DEFAULT_ANSWERS: &str = "../answers.toml";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CliError(String);
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n\n{}", self.0, USAGE)
+    }
+}
+
+impl Error for CliError {}
+
+fn usage_error<S: Into<String>>(msg: S) -> CliError {
+    CliError(msg.into())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Run {
+        day: u8,
+        part: Option<u8>,
+        input: Option<String>,
+        threads: Option<usize>,
+        trace: bool,
+    },
+    Verify {
+        day: Option<u8>,
+        inputs: Option<String>,
+        answers: Option<String>,
+        threads: Option<usize>,
+    },
+    Bench {
+        day: Option<u8>,
+        inputs: Option<String>,
+        options: bench::Options,
+        threads: Option<usize>,
+    },
+}
+
+fn flag_value<'a>(
+    flag: &str,
+    iter: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a str, CliError> {
+    iter.next()
+        .map(|s| s.as_str())
+        .ok_or_else(|| usage_error(format!("missing value for {}", flag)))
+}
+
+fn parse_number(flag: &str, value: &str, min: u8, max: u8) -> Result<u8, CliError> {
+    match value.parse() {
+        Ok(x) if x >= min && x <= max => Ok(x),
+        _ => Err(usage_error(format!(
+            "{} must be between {} and {}, got {:?}",
+            flag, min, max, value
+        ))),
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, CliError> {
+    value
+        .parse()
+        .map_err(|_| usage_error(format!("{} must be a count, got {:?}", flag, value)))
+}
+
+fn parse_threads(value: &str) -> Result<usize, CliError> {
+    match parse_count("--threads", value)? {
+        0 => Err(usage_error("--threads must be at least 1")),
+        n => Ok(n),
+    }
+}
+
+fn parse_run(args: &[String]) -> Result<Command, CliError> {
+    let mut day = None;
+    let mut part = None;
+    let mut input = None;
+    let mut threads = None;
+    let mut trace = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => {
+                day = Some(parse_number("--day", flag_value(arg, &mut iter)?, 1, 25)?)
+            }
+            "--part" | "-p" => {
+                part = Some(parse_number("--part", flag_value(arg, &mut iter)?, 1, 2)?)
+            }
+            "--input" | "-i" => input = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--threads" | "-j" => threads = Some(parse_threads(flag_value(arg, &mut iter)?)?),
+            "--trace" => trace = true,
+            x => return Err(usage_error(format!("unexpected argument {:?}", x))),
+        }
+    }
+
+    let day = day.ok_or_else(|| usage_error("--day is required"))?;
+
+    Ok(Command::Run {
+        day,
+        part,
+        input,
+        threads,
+        trace,
+    })
+}
+
+fn parse_verify(args: &[String]) -> Result<Command, CliError> {
+    let mut day = None;
+    let mut inputs = None;
+    let mut answers = None;
+    let mut threads = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => {
+                day = Some(parse_number("--day", flag_value(arg, &mut iter)?, 1, 25)?)
+            }
+            "--inputs" => inputs = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--answers" | "-a" => answers = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--threads" | "-j" => threads = Some(parse_threads(flag_value(arg, &mut iter)?)?),
+            x => return Err(usage_error(format!("unexpected argument {:?}", x))),
+        }
+    }
+
+    Ok(Command::Verify {
+        day,
+        inputs,
+        answers,
+        threads,
+    })
+}
+
+fn parse_bench(args: &[String]) -> Result<Command, CliError> {
+    let mut day = None;
+    let mut inputs = None;
+    let mut options = bench::Options::default();
+    let mut threads = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => {
+                day = Some(parse_number("--day", flag_value(arg, &mut iter)?, 1, 25)?)
+            }
+            "--inputs" => inputs = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--warmup" => options.warmup = parse_count(arg, flag_value(arg, &mut iter)?)?,
+            "--runs" => match parse_count(arg, flag_value(arg, &mut iter)?)? {
+                0 => return Err(usage_error("--runs must be at least 1")),
+                runs => options.runs = runs,
+            },
+            "--save" => options.save = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--baseline" => options.baseline = Some(flag_value(arg, &mut iter)?.to_owned()),
+            "--threads" | "-j" => threads = Some(parse_threads(flag_value(arg, &mut iter)?)?),
+            x => return Err(usage_error(format!("unexpected argument {:?}", x))),
+        }
+    }
+
+    Ok(Command::Bench {
+        day,
+        inputs,
+        options,
+        threads,
+    })
+}
+
+pub fn parse(args: &[String]) -> Result<Command, CliError> {
+    match args.first().map(|s| s.as_str()) {
+        None | Some("help" | "--help" | "-h") => Ok(Command::Help),
+        Some("run") => parse_run(&args[1..]),
+        Some("verify") => parse_verify(&args[1..]),
+        Some("bench") => parse_bench(&args[1..]),
+        Some(x) => Err(usage_error(format!("unknown command {:?}", x))),
+    }
+}
+
+pub fn execute(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+        }
+        Command::Run {
+            day,
+            part,
+            input,
+            threads,
+            trace,
+        } => {
+            util::set_threads(threads.unwrap_or(0));
+            let solution = runner::find(day).ok_or_else(|| format!("day {} is missing", day))?;
+            let parts: Vec<u8> = match part {
+                Some(p) if p > solution.parts => {
+                    return Err(format!("day {} has no part {}", day, p).into())
+                }
+                Some(p) => vec![p],
+                None => (1..=solution.parts).collect(),
+            };
+
+            let path = input.unwrap_or_else(|| runner::default_input(day));
+            let input = runner::read_input(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+            if trace {
+                solution.trace(&input, &mut io::stdout().lock())?;
+            }
+            let answers = solution.solve(&input, &parts)?;
+            for (part, answer) in parts.iter().zip(answers) {
+                if answer.contains('\n') {
+                    println!("day {} part {}:\n{}", day, part, answer);
+                } else {
+                    println!("day {} part {}: {}", day, part, answer);
+                }
+            }
+        }
+        Command::Verify {
+            day,
+            inputs,
+            answers,
+            threads,
+        } => {
+            util::set_threads(threads.unwrap_or(0));
+            let inputs = inputs.as_deref().unwrap_or(runner::DEFAULT_INPUT_DIR);
+            let answers = answers.as_deref().unwrap_or(DEFAULT_ANSWERS);
+            verify::verify(inputs, answers, day)?;
+        }
+        Command::Bench {
+            day,
+            inputs,
+            options,
+            threads,
+        } => {
+            util::set_threads(threads.unwrap_or(0));
+            let inputs = inputs.as_deref().unwrap_or(runner::DEFAULT_INPUT_DIR);
+            bench::bench(inputs, day, &options)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_ascii_whitespace().map(|s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_parse_run() {
+        assert_eq!(
+            parse(&args("run --day 16 --part 2 --input - --threads 4 --trace")),
+            Ok(Command::Run {
+                day: 16,
+                part: Some(2),
+                input: Some("-".to_owned()),
+                threads: Some(4),
+                trace: true
+            })
+        );
+        assert_eq!(
+            parse(&args("run -d 3")),
+            Ok(Command::Run {
+                day: 3,
+                part: None,
+                input: None,
+                threads: None,
+                trace: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_verify() {
+        assert_eq!(
+            parse(&args("verify --inputs inputs --answers answers.toml")),
+            Ok(Command::Verify {
+                day: None,
+                inputs: Some("inputs".to_owned()),
+                answers: Some("answers.toml".to_owned()),
+                threads: None
+            })
+        );
+        assert!(parse(&args("verify --part 1")).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench() {
+        assert_eq!(
+            parse(&args("bench -d 6 --runs 50 --baseline base.json")),
+            Ok(Command::Bench {
+                day: Some(6),
+                inputs: None,
+                options: bench::Options {
+                    runs: 50,
+                    baseline: Some("base.json".to_owned()),
+                    ..Default::default()
+                },
+                threads: None
+            })
+        );
+        assert!(parse(&args("bench --runs 0")).is_err());
+        assert!(parse(&args("bench --warmup lots")).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&args("run")).is_err());
+        assert!(parse(&args("run --day 26")).is_err());
+        assert!(parse(&args("run --day 1 --part 3")).is_err());
+        assert!(parse(&args("run --day")).is_err());
+        assert!(parse(&args("run --day 1 --threads 0")).is_err());
+        assert!(parse(&args("walk --day 1")).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, hash::Hash};
+
+// the states from step `start` on repeat every `length` steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+// what a simulation measured, up to where it either found a cycle or
+// reached the step that was asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    pub cycle: Option<Cycle>,
+    // the metric after each step, metrics[0] is before the first
+    metrics: Vec<i64>,
+}
+
+impl History {
+    // runs `step` until the state `key` repeats or `limit` steps have been
+    // taken, the key must capture everything that decides future steps
+    pub fn record<S, K, F, G, H>(
+        state: &mut S,
+        limit: usize,
+        mut step: F,
+        key: G,
+        metric: H,
+    ) -> Self
+    where
+        K: Hash + Eq,
+        F: FnMut(&mut S),
+        G: Fn(&S) -> K,
+        H: Fn(&S) -> i64,
+    {
+        let mut seen = HashMap::new();
+        let mut metrics = vec![metric(state)];
+
+        for i in 0.. {
+            if let Some(start) = seen.insert(key(state), i) {
+                return Self {
+                    cycle: Some(Cycle {
+                        start,
+                        length: i - start,
+                    }),
+                    metrics,
+                };
+            }
+            if i == limit {
+                break;
+            }
+            step(state);
+            metrics.push(metric(state));
+        }
+
+        Self {
+            cycle: None,
+            metrics,
+        }
+    }
+
+    // the metric after `n` steps, assuming it changes by the same amount
+    // every time round the cycle
+    pub fn at(&self, n: usize) -> Option<i64> {
+        if let Some(&m) = self.metrics.get(n) {
+            return Some(m);
+        }
+
+        let Cycle { start, length } = self.cycle?;
+        let per_cycle = self.metrics[start + length] - self.metrics[start];
+        let cycles = ((n - start) / length) as i64;
+        let rest = (n - start) % length;
+
+        Some(self.metrics[start + rest] + cycles * per_cycle)
+    }
+}
+
+// the metric after `n` steps from `state`, only simulating until the
+// state repeats
+pub fn extrapolate<S, K, F, G, H>(mut state: S, n: usize, step: F, key: G, metric: H) -> i64
+where
+    K: Hash + Eq,
+    F: FnMut(&mut S),
+    G: Fn(&S) -> K,
+    H: Fn(&S) -> i64,
+{
+    let history = History::record(&mut state, n, step, key, metric);
+    // either the cycle was found or every step up to n was taken
+    history.at(n).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x -> x * x + 1 mod 100 gets into a loop after a few steps
+    fn step(x: &mut u64) {
+        *x = (*x * *x + 1) % 100;
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut x = 3;
+        let history = History::record(&mut x, usize::MAX, step, |&x| x, |&x| x as i64);
+        // 3, 10, 1, 2, 5, 26, 77, 30, 1
+        assert_eq!(
+            history.cycle,
+            Some(Cycle {
+                start: 2,
+                length: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // the running total of the values seen
+        let total = |n| {
+            let mut state = (3, 0);
+            for _ in 0..n {
+                step(&mut state.0);
+                state.1 += state.0 as i64;
+            }
+            state.1
+        };
+        let run = |n| {
+            extrapolate(
+                (3u64, 0i64),
+                n,
+                |s| {
+                    step(&mut s.0);
+                    s.1 += s.0 as i64;
+                },
+                |s| s.0,
+                |s| s.1,
+            )
+        };
+
+        for n in [0, 1, 5, 8, 9, 100, 1001] {
+            assert_eq!(run(n), total(n), "after {} steps", n);
+        }
+    }
+}
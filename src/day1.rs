@@ -1,39 +1,75 @@
-use crate::util;
+use std::error::Error;
 
-pub fn part1(input: &str) {
-    let lines = util::read_lines(input);
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
+
+static TEST_INPUT: &str = "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000";
+
+pub struct Day1;
+
+// the total calories carried by each elf
+fn calories(input: &str) -> Result<Vec<i32>, ParseError> {
     let mut sum = 0;
     let mut sums: Vec<i32> = Vec::new();
-    for line in lines {
-        if line == "" {
+    for (n, line) in util::numbered_lines(input) {
+        if line.is_empty() {
             sums.push(sum);
             sum = 0;
         } else {
-            sum += line.parse::<i32>().expect("not a number");
+            sum += parse::number::<i32>(line, line).map_err(|e| e.at_line(n))?;
         }
     }
+    // the last elf isn't followed by a blank line
+    sums.push(sum);
 
-    let max = sums.iter().max();
-
-    println!("max: {:?}", max)
+    Ok(sums)
 }
 
-pub fn part2(input: &str) {
-    let lines = util::read_lines(input);
-    let mut sum = 0;
-    let mut sums: Vec<i32> = Vec::new();
-    for line in lines {
-        if line == "" {
-            sums.push(sum);
-            sum = 0;
-        } else {
-            sum += line.parse::<i32>().expect("not a number");
-        }
+impl Solution for Day1 {
+    type Input = Vec<i32>;
+    type Answer1 = i32;
+    type Answer2 = i32;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(calories(input)?)
+    }
+
+    fn part1(sums: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(sums.iter().max().copied().unwrap_or(0))
     }
 
-    sums.sort();
+    fn part2(sums: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let mut sums = sums.clone();
+        sums.sort();
 
-    let total: i32 = sums.iter().rev().take(3).sum();
+        Ok(sums.iter().rev().take(3).sum())
+    }
+}
 
-    println!("total: {:?}", total)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let sums = Day1::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day1::part1(&sums).unwrap(), 24000);
+        assert_eq!(Day1::part2(&sums).unwrap(), 45000);
+    }
 }
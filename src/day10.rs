@@ -1,82 +1,460 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
 
-use crate::util;
+use crate::{
+    ocr,
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
-enum Instr {
+static TEST_INPUT: &str = "addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
     Addx(i32),
     Noop,
 }
 
-impl From<&str> for Instr {
-    fn from(value: &str) -> Self {
-        let x: Vec<_> = value.split(" ").collect();
-        match x[0] {
-            "addx" => Self::Addx(x[1].parse().unwrap()),
-            "noop" => Self::Noop,
-            i => panic!("Unknown instruction: {}", i),
+impl FromStr for Instr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse::token(s, 0, "an instruction")? {
+            "addx" => Ok(Self::Addx(parse::number(
+                s,
+                parse::token(s, 1, "a value")?,
+            )?)),
+            "noop" => Ok(Self::Noop),
+            i => Err(ParseError::new(s, i, "unknown instruction")),
         }
     }
 }
 
+impl Display for Instr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Addx(v) => write!(f, "addx {}", v),
+            Self::Noop => write!(f, "noop"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub x: i32,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self { x: 1 }
+    }
+}
+
 impl Instr {
-    fn cycle_length(&self) -> i32 {
+    // how long the instruction takes
+    fn cycles(&self) -> usize {
         match self {
             Self::Addx(_) => 2,
             Self::Noop => 1,
         }
     }
+
+    // what it does, which happens at the end of its last cycle
+    fn execute(&self, regs: &mut Registers) {
+        match self {
+            Self::Addx(inc) => regs.x += inc,
+            Self::Noop => {}
+        }
+    }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut x = 1;
-    let mut cycle = 0;
-    let mut checkpoint = 20;
-    let mut sum = 0;
+// the registers during a cycle, counting from 1, and the instruction
+// that's on its `step`th cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub cycle: usize,
+    pub regs: Registers,
+    pub instr: Instr,
+    pub step: usize,
+}
 
-    for instr in util::read_lines(input).map(|s| Instr::from(s)) {
-        cycle += instr.cycle_length();
+pub trait Observer {
+    fn observe(&mut self, tick: &Tick);
+}
+
+// runs a program a cycle at a time, as an iterator of what the registers
+// hold during each cycle
+pub struct Cpu<'a> {
+    program: &'a [Instr],
+    pc: usize,
+    // cycles spent on the current instruction so far
+    busy: usize,
+    cycle: usize,
+    regs: Registers,
+}
 
-        if cycle >= checkpoint {
-            println!("{} {}", checkpoint, x);
-            sum += x * checkpoint;
-            checkpoint += 40;
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Instr]) -> Self {
+        Self {
+            program,
+            pc: 0,
+            busy: 0,
+            cycle: 0,
+            regs: Registers::default(),
         }
+    }
 
-        match instr {
-            Instr::Addx(inc) => x += inc,
-            Instr::Noop => {}
+    // the program start to end, showing each cycle to the observers
+    pub fn run(self, observers: &mut [&mut dyn Observer]) {
+        for tick in self {
+            for o in observers.iter_mut() {
+                o.observe(&tick);
+            }
         }
     }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = Tick;
 
-    println!("sum: {}", sum);
+    fn next(&mut self) -> Option<Self::Item> {
+        let instr = self.program.get(self.pc)?;
+        self.cycle += 1;
+        self.busy += 1;
+        let tick = Tick {
+            cycle: self.cycle,
+            regs: self.regs,
+            instr: *instr,
+            step: self.busy,
+        };
 
-    Ok(())
+        if self.busy == instr.cycles() {
+            instr.execute(&mut self.regs);
+            self.pc += 1;
+            self.busy = 0;
+        }
+        Some(tick)
+    }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut x: i32 = 1;
-    let mut cycle = 0;
-    let mut screen = [false; 240];
+// writes out the registers every cycle, keeping the first error
+struct Trace<'a> {
+    out: &'a mut dyn Write,
+    result: io::Result<()>,
+}
 
-    for instr in util::read_lines(input).map(|s| Instr::from(s)) {
-        for _ in 0..instr.cycle_length() {
-            screen[cycle] = x - 1 <= cycle as i32 % 40 && x + 1 >= cycle as i32 % 40;
-            cycle += 1;
+impl<'a> Trace<'a> {
+    fn new(out: &'a mut dyn Write) -> Self {
+        Self {
+            out,
+            result: Ok(()),
         }
+    }
+}
 
-        match instr {
-            Instr::Addx(inc) => x += inc,
-            Instr::Noop => {}
+impl Observer for Trace<'_> {
+    fn observe(&mut self, tick: &Tick) {
+        if self.result.is_ok() {
+            self.result = writeln!(
+                self.out,
+                "cycle {:>4}: x={:<4} {} ({}/{})",
+                tick.cycle,
+                tick.regs.x,
+                tick.instr,
+                tick.step,
+                tick.instr.cycles()
+            );
         }
     }
+}
 
-    for r in 0..6 {
-        for c in 0..40 {
-            let ch = if screen[r * 40 + c] { "#" } else { "." };
-            print!("{}", ch);
+// the cycle times x during cycles 20, 60, 100 and so on
+#[derive(Debug, Default)]
+struct SignalStrength {
+    sum: i32,
+}
+
+impl Observer for SignalStrength {
+    fn observe(&mut self, tick: &Tick) {
+        if tick.cycle % 40 == 20 {
+            self.sum += tick.cycle as i32 * tick.regs.x;
         }
-        println!();
     }
+}
+
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
 
-    Ok(())
+// draws a pixel each cycle, lit when the 3 pixel wide sprite at x covers
+// it
+struct Crt {
+    pixels: [bool; CRT_WIDTH * CRT_HEIGHT],
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self {
+            pixels: [false; CRT_WIDTH * CRT_HEIGHT],
+        }
+    }
+}
+
+impl Observer for Crt {
+    fn observe(&mut self, tick: &Tick) {
+        let pixel = tick.cycle - 1;
+        if let Some(lit) = self.pixels.get_mut(pixel) {
+            let column = (pixel % CRT_WIDTH) as i32;
+            *lit = (tick.regs.x - 1..=tick.regs.x + 1).contains(&column);
+        }
+    }
+}
+
+impl Display for Crt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<String> = self
+            .pixels
+            .chunks(CRT_WIDTH)
+            .map(|row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect())
+            .collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Vec<Instr>;
+    type Answer1 = i32;
+    type Answer2 = String;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::parse_lines(input)?)
+    }
+
+    fn part1(instrs: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let mut signal = SignalStrength::default();
+        Cpu::new(instrs).run(&mut [&mut signal]);
+        Ok(signal.sum)
+    }
+
+    fn part2(instrs: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let mut crt = Crt::default();
+        Cpu::new(instrs).run(&mut [&mut crt]);
+        Ok(ocr::read(&crt.to_string())?)
+    }
+
+    fn trace(instrs: &Self::Input, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let mut trace = Trace::new(out);
+        Cpu::new(instrs).run(&mut [&mut trace]);
+        Ok(trace.result?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        let program = Day10::parse("noop\naddx 3\naddx -5").unwrap();
+        let mut out = Vec::new();
+        Day10::trace(&program, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cycle    1: x=1    noop (1/1)
+cycle    2: x=1    addx 3 (1/2)
+cycle    3: x=1    addx 3 (2/2)
+cycle    4: x=4    addx -5 (1/2)
+cycle    5: x=4    addx -5 (2/2)
+"
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let instrs = Day10::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day10::part1(&instrs).unwrap(), 13140);
+        // the example only draws a test pattern
+        let err = Day10::part2(&instrs).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unrecognised letter 1 at column 1"));
+
+        let mut crt = Crt::default();
+        Cpu::new(&instrs).run(&mut [&mut crt]);
+        assert_eq!(
+            crt.to_string(),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
+
+    #[test]
+    fn test_cpu() {
+        let program: Vec<Instr> = util::parse_lines("noop\naddx 3\naddx -5").unwrap();
+        let xs: Vec<_> = Cpu::new(&program).map(|t| (t.cycle, t.regs.x)).collect();
+        assert_eq!(xs, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+
+        // both observers from one run
+        let program = Day10::parse(TEST_INPUT).unwrap();
+        let mut signal = SignalStrength::default();
+        let mut crt = Crt::default();
+        Cpu::new(&program).run(&mut [&mut signal, &mut crt]);
+        assert_eq!(signal.sum, 13140);
+        assert!(crt.to_string().starts_with("##..##"));
+    }
 }
@@ -1,215 +1,768 @@
-use std::{collections::VecDeque, error::Error};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+    bignum::BigUint,
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
+
+static TEST_INPUT: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
-    Add(i64),
-    Mul(i64),
-    Square,
+    Add,
+    Sub,
+    Mul,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Sub => write!(f, "-"),
+            Op::Mul => write!(f, "*"),
+        }
+    }
+}
+
+// how a monkey changes the worry level of an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Num(i64),
+    Op(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Num(x) => write!(f, "{}", x),
+            Expr::Op(a, op, b) => {
+                // brackets where the precedence needs them
+                let bracket = |e: &Expr, right: bool| match e {
+                    Expr::Op(_, inner, _) => {
+                        (*op == Op::Mul && *inner != Op::Mul)
+                            || (right && *op == Op::Sub && *inner != Op::Mul)
+                    }
+                    _ => false,
+                };
+                for (e, right) in [(a, false), (b, true)] {
+                    if right {
+                        write!(f, " {} ", op)?;
+                    }
+                    if bracket(e, right) {
+                        write!(f, "({})", e)?;
+                    } else {
+                        write!(f, "{}", e)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// numbers, words and single characters, as slices of `s` so errors can
+// point at them
+fn tokens(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        if c.is_alphanumeric() {
+            while let Some(&(j, d)) = chars.peek() {
+                if !d.is_alphanumeric() {
+                    break;
+                }
+                end = j + d.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(&s[i..end]);
+    }
+    tokens
+}
+
+// the usual precedence, * before + and -, all left to right
+struct ExprParser<'a> {
+    source: &'a str,
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while let Some(op) = self.peek().and_then(|t| match t {
+            "+" => Some(Op::Add),
+            "-" => Some(Op::Sub),
+            _ => None,
+        }) {
+            self.pos += 1;
+            expr = Expr::Op(Box::new(expr), op, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while self.peek() == Some("*") {
+            self.pos += 1;
+            expr = Expr::Op(Box::new(expr), Op::Mul, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let expected = "expected a number, `old` or `(`";
+        match self.next() {
+            Some("old") => Ok(Expr::Old),
+            Some("(") => {
+                let expr = self.expr()?;
+                match self.next() {
+                    Some(")") => Ok(expr),
+                    Some(t) => Err(ParseError::new(self.source, t, "expected `)`")),
+                    None => Err(ParseError::missing(self.source, "expected `)`")),
+                }
+            }
+            Some(t) if t.starts_with(|c: char| c.is_ascii_digit()) => {
+                Ok(Expr::Num(parse::number(self.source, t)?))
+            }
+            Some(t) => Err(ParseError::new(self.source, t, expected)),
+            None => Err(ParseError::missing(self.source, expected)),
+        }
+    }
+}
+
+// e.g. new = old * 19
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rhs = s
+            .strip_prefix("new =")
+            .ok_or_else(|| ParseError::new(s, s, "expected `new = `"))?;
+        let mut parser = ExprParser {
+            source: s,
+            tokens: tokens(rhs),
+            pos: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(t) => Err(ParseError::new(s, t, "expected +, - or *")),
+        }
+    }
 }
 
-impl Op {
-    fn apply(&self, v: i64) -> i64 {
+// what the worry levels can be kept in, None is an overflow
+trait Worry: Clone + Display {
+    fn constant(n: i64) -> Option<Self>;
+    fn apply(&self, op: Op, other: &Self) -> Option<Self>;
+    fn divisible_by(&self, div: u32) -> bool;
+}
+
+impl Worry for i64 {
+    fn constant(n: i64) -> Option<Self> {
+        Some(n)
+    }
+
+    fn apply(&self, op: Op, other: &Self) -> Option<Self> {
+        match op {
+            Op::Add => self.checked_add(*other),
+            Op::Sub => self.checked_sub(*other),
+            Op::Mul => self.checked_mul(*other),
+        }
+    }
+
+    fn divisible_by(&self, div: u32) -> bool {
+        self % div as i64 == 0
+    }
+}
+
+// exact, but worry can't go below zero
+impl Worry for BigUint {
+    fn constant(n: i64) -> Option<Self> {
+        u64::try_from(n).ok().map(BigUint::from)
+    }
+
+    fn apply(&self, op: Op, other: &Self) -> Option<Self> {
+        match op {
+            Op::Add => Some(self.add(other)),
+            Op::Sub => self.checked_sub(other),
+            Op::Mul => Some(self.mul(other)),
+        }
+    }
+
+    fn divisible_by(&self, div: u32) -> bool {
+        self.div_rem(div).1 == 0
+    }
+}
+
+impl Expr {
+    fn eval<W: Worry>(&self, old: &W) -> Option<W> {
         match self {
-            Self::Add(x) => v + x,
-            Self::Mul(x) => v * x,
-            Self::Square => v * v,
+            Expr::Old => Some(old.clone()),
+            Expr::Num(x) => W::constant(*x),
+            Expr::Op(a, op, b) => a.eval(old)?.apply(*op, &b.eval(old)?),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Monkey {
+    id: usize,
     items: VecDeque<i64>,
-    inspections: usize,
-    op: Op,
-    div: i64,
+    op: Expr,
+    div: u32,
     dest_true: usize,
     dest_false: usize,
 }
 
-impl Monkey {
-    fn dest(&self, v: i64) -> usize {
-        if v % self.div == 0 {
-            self.dest_true
-        } else {
-            self.dest_false
-        }
+// the value after `prefix` on a line of a monkey block
+fn field<'a>(line: Option<&'a str>, prefix: &str) -> Result<&'a str, ParseError> {
+    let line = line.ok_or_else(|| ParseError::missing("", format!("expected {:?}", prefix)))?;
+    line.trim_start()
+        .strip_prefix(prefix)
+        .map(|s| s.trim())
+        .ok_or_else(|| ParseError::new(line, line.trim(), format!("expected {:?}", prefix)))
+}
+
+// Monkey 0:
+//   Starting items: 79, 98
+//   Operation: new = old * 19
+//   Test: divisible by 23
+//     If true: throw to monkey 2
+//     If false: throw to monkey 3
+impl FromStr for Monkey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<_> = s.lines().collect();
+        let line = |n: usize| lines.get(n).copied();
+        // errors are reported against the line they were found on
+        let at = |n: usize| move |e: ParseError| e.at_line(n + 1);
+
+        let id = field(line(0), "Monkey").map_err(at(0))?;
+        let id = parse::number(lines[0], id.strip_suffix(':').unwrap_or(id)).map_err(at(0))?;
+
+        let items = field(line(1), "Starting items:").map_err(at(1))?;
+        let items = items
+            .split(", ")
+            .filter(|x| !x.is_empty())
+            .map(|x| parse::number(lines[1], x))
+            .collect::<Result<_, _>>()
+            .map_err(at(1))?;
+
+        let op = field(line(2), "Operation:").map_err(at(2))?;
+        let op = op
+            .parse()
+            .map_err(|e: ParseError| e.within(lines[2], op))
+            .map_err(at(2))?;
+
+        let number = |n: usize, prefix: &str| {
+            field(line(n), prefix)
+                .and_then(|x| parse::number(lines[n], x))
+                .map_err(at(n))
+        };
+        let div = field(line(3), "Test: divisible by").map_err(at(3))?;
+        let div = match parse::number(lines[3], div).map_err(at(3))? {
+            0 => {
+                let err = ParseError::new(lines[3], div, "expected a divisor above 0");
+                return Err(at(3)(err));
+            }
+            x => x,
+        };
+        let dest_true = number(4, "If true: throw to monkey")?;
+        let dest_false = number(5, "If false: throw to monkey")?;
+
+        Ok(Self {
+            id,
+            items,
+            op,
+            div,
+            dest_true,
+            dest_false,
+        })
     }
 }
 
-#[derive(Debug)]
-struct MonkeyBusiness {
+#[derive(Debug, Clone)]
+pub struct MonkeyBusiness {
     monkeys: Vec<Monkey>,
 }
 
 impl MonkeyBusiness {
-    fn round(&mut self) {
-        for i in 0..self.monkeys.len() {
-            while let Some(item) = self.monkeys[i].items.pop_front() {
-                self.monkeys[i].inspections += 1;
+    // monkey `i` inspecting an item, giving who it's thrown to and the new
+    // worry level
+    fn inspect<W: Worry>(
+        &self,
+        i: usize,
+        old: &W,
+        relieve: &impl Fn(W) -> W,
+    ) -> Result<(usize, W), String> {
+        let monkey = &self.monkeys[i];
+        let new = monkey.op.eval(old).ok_or_else(|| {
+            format!(
+                "monkey {} overflowed working out {} with old = {}",
+                i, monkey.op, old
+            )
+        })?;
+        let new = relieve(new);
+        let dest = if new.divisible_by(monkey.div) {
+            monkey.dest_true
+        } else {
+            monkey.dest_false
+        };
+        if dest >= self.monkeys.len() {
+            return Err(format!("monkey {} throws to missing monkey {}", i, dest));
+        }
+        // it would never finish its turn
+        if dest == i {
+            return Err(format!("monkey {} throws to itself", i));
+        }
+        Ok((dest, new))
+    }
+
+    // how many items each monkey inspected, `relieve` is applied to the
+    // worry level after every inspection
+    fn simulate<W: Worry>(
+        &self,
+        rounds: usize,
+        relieve: impl Fn(W) -> W,
+    ) -> Result<Vec<usize>, String> {
+        let mut items = Vec::new();
+        for m in self.monkeys.iter() {
+            let start: Option<VecDeque<W>> = m.items.iter().map(|&x| W::constant(x)).collect();
+            items.push(start.ok_or("a starting worry level doesn't fit")?);
+        }
+        let mut inspections = vec![0; self.monkeys.len()];
+        for _ in 0..rounds {
+            for i in 0..self.monkeys.len() {
+                while let Some(old) = items[i].pop_front() {
+                    inspections[i] += 1;
+                    let (dest, new) = self.inspect(i, &old, &relieve)?;
+                    items[dest].push_back(new);
+                }
+            }
+        }
+        Ok(inspections)
+    }
+
+    // every test passes or fails the same modulo this, so worry levels can
+    // be kept below it
+    fn modulus(&self) -> Result<i64, String> {
+        self.monkeys.iter().try_fold(1i64, |m, monkey| {
+            let div = monkey.div as i64;
+            let lcm = m / gcd(m, div);
+            lcm.checked_mul(div)
+                .ok_or_else(|| "the divisors' common multiple doesn't fit in an i64".to_owned())
+        })
+    }
+
+    // dividing by 3, or reducing modulo the divisors
+    fn relief(&self, relief: bool) -> Result<Box<dyn Fn(i64) -> i64>, String> {
+        if relief {
+            Ok(Box::new(|w| w / 3))
+        } else {
+            let m = self.modulus()?;
+            Ok(Box::new(move |w| w.rem_euclid(m)))
+        }
+    }
+
+    // with the worry levels reduced modulo the divisors, so any round count
+    // works as long as the operations fit in an i64
+    fn inspections(&self, rounds: usize, relief: bool) -> Result<Vec<usize>, String> {
+        self.simulate(rounds, self.relief(relief)?)
+    }
 
-                let new = self.monkeys[i].op.apply(item) / 3;
-                let dest = self.monkeys[i].dest(new);
+    // with exact worry levels, to check the reduced ones against, only
+    // practical for a handful of rounds as the numbers grow quickly
+    fn exact_inspections(&self, rounds: usize, relief: bool) -> Result<Vec<usize>, String> {
+        if relief {
+            self.simulate(rounds, |w: BigUint| w.div_rem(3).0)
+        } else {
+            self.simulate(rounds, |w: BigUint| w)
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// an item being thrown, with its new worry level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hop {
+    round: usize,
+    from: usize,
+    to: usize,
+    worry: i64,
+}
+
+// everywhere one item went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lineage {
+    monkey: usize,
+    worry: i64,
+    hops: Vec<Hop>,
+}
+
+impl Display for Lineage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at monkey {}", self.worry, self.monkey)?;
+        for hop in self.hops.iter() {
+            write!(
+                f,
+                "\n  round {}: {} -> {} with {}",
+                hop.round, hop.from, hop.to, hop.worry
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// the monkey holding an item at the start of a round and its worry level
+type ItemState = (usize, i64);
+
+// the inspections of an item's first rounds, which repeat every `len`
+// rounds after `start` if it has a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemRounds {
+    cycle: Option<(usize, usize)>,
+    // inspections by each monkey in the first n rounds
+    totals: Vec<Vec<usize>>,
+}
 
-                self.monkeys[dest].items.push_back(new);
+impl ItemRounds {
+    // inspections by each monkey after any number of rounds, None if it
+    // wasn't followed that far or there are too many to count
+    fn inspections(&self, rounds: usize) -> Option<Vec<usize>> {
+        if rounds < self.totals.len() {
+            return Some(self.totals[rounds].clone());
+        }
+        let (start, len) = self.cycle?;
+        let (cycles, rest) = ((rounds - start) / len, (rounds - start) % len);
+        let (before, after) = (&self.totals[start], &self.totals[start + len]);
+        self.totals[start + rest]
+            .iter()
+            .zip(before.iter().zip(after))
+            .map(|(&n, (&b, &a))| (a - b).checked_mul(cycles)?.checked_add(n))
+            .collect()
+    }
+}
+
+impl MonkeyBusiness {
+    // items don't affect each other, so each can be followed on its own
+    fn items(&self) -> impl Iterator<Item = ItemState> + '_ {
+        self.monkeys
+            .iter()
+            .enumerate()
+            .flat_map(|(i, m)| m.items.iter().map(move |&w| (i, w)))
+    }
+
+    // one round for a single item, which carries on until it's thrown to
+    // a monkey that's already had its turn
+    fn item_round(
+        &self,
+        round: usize,
+        (mut monkey, mut worry): ItemState,
+        relieve: &impl Fn(i64) -> i64,
+        hops: &mut Vec<Hop>,
+    ) -> Result<ItemState, String> {
+        loop {
+            let (to, new) = self.inspect(monkey, &worry, relieve)?;
+            hops.push(Hop {
+                round,
+                from: monkey,
+                to,
+                worry: new,
+            });
+            (monkey, worry) = (to, new);
+            if to < hops.last().unwrap().from {
+                return Ok((monkey, worry));
             }
         }
     }
 
-    fn round_v2(&mut self, div: i64) {
-        for i in 0..self.monkeys.len() {
-            while let Some(item) = self.monkeys[i].items.pop_front() {
-                self.monkeys[i].inspections += 1;
+    // every throw of every item, in the order the items start in
+    fn trace(&self, rounds: usize, relief: bool) -> Result<Vec<Lineage>, String> {
+        let relieve = self.relief(relief)?;
+        self.items()
+            .map(|(monkey, worry)| {
+                let mut hops = Vec::new();
+                let mut state = (monkey, worry);
+                for round in 1..=rounds {
+                    state = self.item_round(round, state, &relieve, &mut hops)?;
+                }
+                Ok(Lineage {
+                    monkey,
+                    worry,
+                    hops,
+                })
+            })
+            .collect()
+    }
+
+    // follows an item until it's back where it was at the start of an
+    // earlier round, or for `rounds` rounds if that's sooner
+    fn item_rounds(
+        &self,
+        mut state: ItemState,
+        relieve: &impl Fn(i64) -> i64,
+        rounds: usize,
+    ) -> Result<ItemRounds, String> {
+        let mut seen = HashMap::new();
+        let mut totals = vec![vec![0; self.monkeys.len()]];
+        let mut hops = Vec::new();
+        loop {
+            let round = totals.len() - 1;
+            if let Some(&start) = seen.get(&state) {
+                let cycle = Some((start, round - start));
+                return Ok(ItemRounds { cycle, totals });
+            }
+            if round == rounds {
+                return Ok(ItemRounds {
+                    cycle: None,
+                    totals,
+                });
+            }
+            seen.insert(state, round);
 
-                let new = self.monkeys[i].op.apply(item) % div;
-                let dest = self.monkeys[i].dest(new);
+            hops.clear();
+            state = self.item_round(round + 1, state, relieve, &mut hops)?;
+            let mut next = totals[round].clone();
+            for hop in hops.iter() {
+                next[hop.from] += 1;
+            }
+            totals.push(next);
+        }
+    }
 
-                self.monkeys[dest].items.push_back(new);
+    // the inspections after any number of rounds, from each item's cycle
+    // rather than simulating them all
+    fn projected_inspections(&self, rounds: usize, relief: bool) -> Result<Vec<usize>, String> {
+        let relieve = self.relief(relief)?;
+        let mut inspections = vec![0; self.monkeys.len()];
+        for state in self.items() {
+            let too_many = || format!("too many inspections to count in {} rounds", rounds);
+            let counts = self
+                .item_rounds(state, &relieve, rounds)?
+                .inspections(rounds)
+                .ok_or_else(too_many)?;
+            for (total, n) in inspections.iter_mut().zip(counts) {
+                *total = n.checked_add(*total).ok_or_else(too_many)?;
             }
         }
+        Ok(inspections)
     }
 }
 
-fn test_input() -> MonkeyBusiness {
-    let monkeys = vec![
-        Monkey {
-            items: VecDeque::from([79, 98]),
-            inspections: 0,
-            op: Op::Mul(19),
-            div: 23,
-            dest_true: 2,
-            dest_false: 3,
-        },
-        Monkey {
-            items: VecDeque::from([54, 65, 75, 74]),
-            inspections: 0,
-            op: Op::Add(6),
-            div: 19,
-            dest_true: 2,
-            dest_false: 0,
-        },
-        Monkey {
-            items: VecDeque::from([79, 60, 97]),
-            inspections: 0,
-            op: Op::Square,
-            div: 13,
-            dest_true: 1,
-            dest_false: 3,
-        },
-        Monkey {
-            items: VecDeque::from([74]),
-            inspections: 0,
-            op: Op::Add(3),
-            div: 17,
-            dest_true: 0,
-            dest_false: 1,
-        },
-    ];
-    MonkeyBusiness { monkeys }
-}
-
-fn input() -> MonkeyBusiness {
-    let monkeys = vec![
-        Monkey {
-            items: VecDeque::from([72, 64, 51, 57, 93, 97, 68]),
-            inspections: 0,
-            op: Op::Mul(19),
-            div: 17,
-            dest_true: 4,
-            dest_false: 7,
-        },
-        Monkey {
-            items: VecDeque::from([62]),
-            inspections: 0,
-            op: Op::Mul(11),
-            div: 3,
-            dest_true: 3,
-            dest_false: 2,
-        },
-        Monkey {
-            items: VecDeque::from([57, 94, 69, 79, 72]),
-            inspections: 0,
-            op: Op::Add(6),
-            div: 19,
-            dest_true: 0,
-            dest_false: 4,
-        },
-        Monkey {
-            items: VecDeque::from([80, 64, 92, 93, 64, 56]),
-            inspections: 0,
-            op: Op::Add(5),
-            div: 7,
-            dest_true: 2,
-            dest_false: 0,
-        },
-        Monkey {
-            items: VecDeque::from([70, 88, 95, 99, 78, 72, 65, 94]),
-            inspections: 0,
-            op: Op::Add(7),
-            div: 2,
-            dest_true: 7,
-            dest_false: 5,
-        },
-        Monkey {
-            items: VecDeque::from([57, 95, 81, 61]),
-            inspections: 0,
-            op: Op::Square,
-            div: 5,
-            dest_true: 1,
-            dest_false: 6,
-        },
-        Monkey {
-            items: VecDeque::from([79, 99]),
-            inspections: 0,
-            op: Op::Add(2),
-            div: 11,
-            dest_true: 3,
-            dest_false: 1,
-        },
-        Monkey {
-            items: VecDeque::from([68, 98, 62]),
-            inspections: 0,
-            op: Op::Add(3),
-            div: 13,
-            dest_true: 5,
-            dest_false: 6,
-        },
-    ];
-    MonkeyBusiness { monkeys }
-}
-
-pub fn part1(_: &str) -> Result<(), Box<dyn Error>> {
-    let mut mb = input();
-    for _ in 0..20 {
-        mb.round();
-    }
-
-    let mut inspections: Vec<_> = mb.monkeys.iter().map(|m| m.inspections).collect();
+// the inspections of the two busiest monkeys multiplied
+fn monkey_business(mut inspections: Vec<usize>) -> usize {
     inspections.sort();
-    inspections.reverse();
+    inspections.iter().rev().take(2).product()
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = MonkeyBusiness;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        let monkeys = util::blocks(input)
+            .enumerate()
+            .map(|(i, (offset, block))| {
+                let monkey: Monkey = block
+                    .parse()
+                    .map_err(|e: ParseError| e.offset_lines(offset))?;
+                // monkeys throw to each other by position
+                if monkey.id != i {
+                    let line = block.lines().next().unwrap_or_default();
+                    let id = parse::token(line, 1, "a monkey number")?;
+                    let id = id.strip_suffix(':').unwrap_or(id);
+                    let err = ParseError::new(line, id, format!("expected monkey {}", i));
+                    return Err(err.at_line(offset + 1));
+                }
+                Ok(monkey)
+            })
+            .collect::<Result<_, _>>()?;
 
-    let score: usize = inspections.iter().take(2).product();
+        Ok(MonkeyBusiness { monkeys })
+    }
 
-    println!("score: {}", score);
+    fn part1(mb: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(monkey_business(mb.inspections(20, true)?))
+    }
 
-    Ok(())
+    fn part2(mb: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(monkey_business(mb.inspections(10000, false)?))
+    }
 }
 
-pub fn part2(_: &str) -> Result<(), Box<dyn Error>> {
-    let mut mb = input();
-    let div: i64 = mb.monkeys.iter().map(|m| m.div).product();
-    for _ in 0..10000 {
-        mb.round_v2(div);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mb = Day11::parse(TEST_INPUT).unwrap();
+        assert_eq!(mb.monkeys.len(), 4);
+        assert_eq!(mb.monkeys[1].items, VecDeque::from([54, 65, 75, 74]));
+        assert_eq!(mb.monkeys[2].op.to_string(), "old * old");
+        assert_eq!(mb.monkeys[3].div, 17);
+        assert_eq!(mb.monkeys[3].dest_true, 0);
+        assert_eq!(mb.monkeys[3].dest_false, 1);
+        assert_eq!(Day11::part1(&mb).unwrap(), 10605);
     }
 
-    let mut inspections: Vec<_> = mb.monkeys.iter().map(|m| m.inspections).collect();
-    println!("inpections: {:?}", inspections);
-    inspections.sort();
-    inspections.reverse();
+    #[test]
+    fn test_parse_error() {
+        let input = TEST_INPUT.replace("old + 3", "old / 3");
+        let err = Day11::parse(&input).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 24, column 24: expected +, - or *, found \"/\""
+        );
+
+        let input = TEST_INPUT.replace("Monkey 2:", "Monkey 3:");
+        let err = Day11::parse(&input).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 15, column 8: expected monkey 2, found \"3\""
+        );
+        let input = TEST_INPUT.replace("Monkey 1:", "Monkey one:");
+        let err = Day11::parse(&input).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 8, column 8: expected a number, found \"one\""
+        );
+    }
+
+    #[test]
+    fn test_expr() {
+        let expr: Expr = "new = (old + 2) * old - 3 * 4".parse().unwrap();
+        assert_eq!(expr.to_string(), "(old + 2) * old - 3 * 4");
+        assert_eq!(expr.eval(&5i64), Some(23));
+        let expr: Expr = "new = old - (old - 1)".parse().unwrap();
+        assert_eq!(expr.to_string(), "old - (old - 1)");
+        assert_eq!(expr.eval(&BigUint::from(9)), Some(BigUint::from(1)));
+
+        let err = "new = old * (old + 1".parse::<Expr>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "column 21: expected `)`, found end of input"
+        );
+        let err = "new = old * * 2".parse::<Expr>().unwrap_err();
+        assert_eq!(err.column(), 13);
+    }
+
+    #[test]
+    fn test_exact() {
+        let mb = Day11::parse(TEST_INPUT).unwrap();
+        assert_eq!(mb.exact_inspections(20, true), mb.inspections(20, true));
+        assert_eq!(mb.exact_inspections(10, false), mb.inspections(10, false));
+        assert_eq!(mb.inspections(20, false).unwrap(), [99, 97, 8, 103]);
 
-    let score: usize = inspections.iter().take(2).product();
+        // without relief or the modulus the worry levels soon overflow
+        let err = mb.simulate(20, |w: i64| w).unwrap_err();
+        assert!(err.contains("overflowed working out old * old"), "{}", err);
+    }
 
-    println!("score: {}", score);
+    #[test]
+    fn test_trace() {
+        let mb = Day11::parse(TEST_INPUT).unwrap();
+        let lineages = mb.trace(20, true).unwrap();
+        assert_eq!(lineages.len(), 10);
+        assert_eq!(
+            lineages[0].to_string().lines().take(3).collect::<Vec<_>>(),
+            [
+                "79 at monkey 0",
+                "  round 1: 0 -> 3 with 500",
+                "  round 1: 3 -> 1 with 167"
+            ]
+        );
+        // the throws from every item add up to the whole simulation
+        let mut inspections = vec![0; 4];
+        for hop in lineages.iter().flat_map(|l| l.hops.iter()) {
+            inspections[hop.from] += 1;
+        }
+        assert_eq!(inspections, mb.inspections(20, true).unwrap());
+    }
 
-    Ok(())
+    #[test]
+    fn test_projected_inspections() {
+        let mb = Day11::parse(TEST_INPUT).unwrap();
+        let item = mb.item_rounds((0, 79), &mb.relief(false).unwrap(), usize::MAX);
+        let (_, len) = item.unwrap().cycle.unwrap();
+        assert!(len > 0);
+        for (rounds, relief) in [(20, true), (1000, false), (10000, false)] {
+            assert_eq!(
+                mb.projected_inspections(rounds, relief),
+                mb.inspections(rounds, relief)
+            );
+        }
+        let inspections = mb.projected_inspections(1_000_000_000_000, false).unwrap();
+        assert!(inspections[0] > 1_000_000_000_000);
+        assert!(inspections[2] > 100_000_000_000);
+    }
+
+    #[test]
+    fn test_example() {
+        let mb = Day11::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day11::part1(&mb).unwrap(), 10605);
+        assert_eq!(Day11::part2(&mb).unwrap(), 2713310158);
+    }
 }
@@ -1,9 +1,10 @@
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    error::Error,
-};
+use std::{collections::VecDeque, error::Error};
 
-use crate::{graph::Graph, util};
+use crate::{
+    grid::{Grid, Point},
+    parse::ParseError,
+    solution::Solution,
+};
 
 static TEST_INPUT: &str = "Sabqponm
 abcryxxl
@@ -11,180 +12,181 @@ accszExk
 acctuvwj
 abdefghi";
 
-fn parse(input: &str) -> Vec<Vec<u8>> {
-    util::read_lines(input)
-        .map(|line| line.chars().map(|c| c as u8).collect())
-        .collect()
+fn parse(input: &str) -> Result<Grid<u8>, ParseError> {
+    Grid::parse(input, "a height a-z, S or E", |c| {
+        matches!(c, 'a'..='z' | 'S' | 'E').then_some(c as u8)
+    })
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Loc(usize, usize);
-
-impl Loc {
-    fn next(&self) -> Vec<Self> {
-        let mut next = vec![Loc(self.0, self.1 + 1), Loc(self.0 + 1, self.1)];
-        if self.0 > 0 {
-            next.push(Loc(self.0 - 1, self.1));
-        }
-        if self.1 > 0 {
-            next.push(Loc(self.0, self.1 - 1));
-        }
-        next
-    }
+// the places visited in order, from the first to the last
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    path: Vec<Point>,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
+impl Route {
+    pub fn steps(&self) -> usize {
+        self.path.len() - 1
+    }
 }
 
-struct Map {
-    start: Loc,
-    end: Loc,
-    height: HashMap<Loc, u8>,
+pub struct Map {
+    start: Point,
+    end: Point,
+    height: Grid<u8>,
 }
 
 impl Map {
-    fn new(map: Vec<Vec<u8>>) -> Self {
-        let mut start = Loc(0, 0);
-        let mut end = Loc(0, 0);
-        let mut height = HashMap::new();
-        for (y, row) in map.iter().enumerate() {
-            for (x, v) in row.iter().enumerate() {
-                let loc = Loc(x, y);
-                let mut h = *v;
-                if h == 'S' as u8 {
-                    start = loc;
-                    h = 'a' as u8;
-                } else if h == 'E' as u8 {
-                    end = loc;
-                    h = 'z' as u8;
-                }
-
-                height.insert(loc, h);
-            }
-        }
-
-        Self { height, start, end }
+    fn new(mut height: Grid<u8>) -> Result<Self, ParseError> {
+        let missing = |what| ParseError::missing("", format!("expected {} in the map", what));
+        let start = height.find(|&h| h == b'S').ok_or_else(|| missing("S"))?;
+        let end = height.find(|&h| h == b'E').ok_or_else(|| missing("E"))?;
+        height[start] = b'a';
+        height[end] = b'z';
+
+        Ok(Self { height, start, end })
     }
 
-    fn possible(&self, loc: &Loc, dir: Direction) -> Vec<Loc> {
-        // get the current height
-        let h = self.height.get(loc).unwrap();
-        loc.next()
-            .into_iter()
-            .filter(|l| match self.height.get(l) {
-                None => false,
-                Some(lh) => match dir {
-                    Direction::Down => *h - 1 <= *lh,
-                    Direction::Up => *h + 1 >= *lh,
-                },
-            })
-            .collect()
+    // climbing at most one higher, but dropping any amount
+    fn can_step(&self, from: Point, to: Point) -> bool {
+        self.height[to] <= self.height[from] + 1
     }
 
-    fn find_path_from_start(&self) -> usize {
-        let mut graph = Graph::new();
-        for loc in self.height.keys() {
-            for p in self.possible(loc, Direction::Up) {
-                graph.add_edge(*loc, p, 1);
+    // breadth first from all of `starts` at once until a point that passes
+    // `goal`, remembering where each point was first reached from
+    fn search(
+        &self,
+        starts: impl IntoIterator<Item = Point>,
+        goal: impl Fn(Point) -> bool,
+        can_step: impl Fn(Point, Point) -> bool,
+    ) -> Option<Route> {
+        let mut came_from = self.height.map(|_| None);
+        let mut queue = VecDeque::new();
+        for start in starts {
+            came_from[start] = Some(start);
+            queue.push_back(start);
+        }
+
+        while let Some(p) = queue.pop_front() {
+            if goal(p) {
+                let mut path = vec![p];
+                while let Some(prev) = came_from[*path.last().unwrap()] {
+                    if prev == *path.last().unwrap() {
+                        break;
+                    }
+                    path.push(prev);
+                }
+                path.reverse();
+                return Some(Route { path });
+            }
+            for next in self.height.neighbours4(p) {
+                if came_from[next].is_none() && can_step(p, next) {
+                    came_from[next] = Some(p);
+                    queue.push_back(next);
+                }
             }
         }
-        graph.shortest_path(&self.start, &self.end).unwrap()
+        None
     }
 
-    fn find_path_from_end(&self) -> usize {
-        Hike::hike(&self, &self.end, Direction::Down, |l| l == &self.start).unwrap()
+    pub fn route_from_start(&self) -> Result<Route, String> {
+        self.search([self.start], |p| p == self.end, |a, b| self.can_step(a, b))
+            .ok_or_else(|| "no route from S to E".to_owned())
     }
-}
-
-struct Hike<'a, F>
-where
-    F: Fn(&Loc) -> bool,
-{
-    map: &'a Map,
-    // map of points that have been reached and how many steps it took
-    seen: HashMap<Loc, usize>,
-    goal: F,
-}
 
-impl<'a, F> Hike<'a, F>
-where
-    F: Fn(&Loc) -> bool,
-{
-    fn hike(map: &'a Map, start: &Loc, dir: Direction, goal: F) -> Option<usize> {
-        let mut seen = HashMap::new();
-        seen.insert(start.to_owned(), 0);
-        let mut hiker = Self { seen, map, goal };
-        hiker.find_path(start, 0, dir)
+    // searches backwards from the end, so every `a` is tried at once
+    pub fn route_from_lowest(&self) -> Result<Route, String> {
+        let mut route = self
+            .search(
+                [self.end],
+                |p| self.height[p] == b'a',
+                |a, b| self.can_step(b, a),
+            )
+            .ok_or_else(|| "no route from any a to E".to_owned())?;
+        route.path.reverse();
+        Ok(route)
     }
 
-    fn find_path(&mut self, loc: &Loc, steps: usize, dir: Direction) -> Option<usize> {
-        let steps = steps + 1;
-        let mut remaining = Vec::new();
-
-        let possible = self.map.possible(&loc, dir);
-
-        println!("{:?} ({})->", loc, self.map.height[loc]);
-        for p in possible.iter() {
-            println!("  {:?} ({})", p, self.map.height[&p]);
+    // the map with an arrow on each step of the route towards the next one
+    pub fn render(&self, route: &Route) -> String {
+        let mut map = self.height.map(|_| '.');
+        for step in route.path.windows(2) {
+            map[step[0]] = match step[1] - step[0] {
+                Point::UP => '^',
+                Point::DOWN => 'v',
+                Point::LEFT => '<',
+                Point::RIGHT => '>',
+                _ => panic!("{:?} to {:?} isn't a single step", step[0], step[1]),
+            };
         }
+        if let Some(&last) = route.path.last() {
+            map[last] = 'E';
+        }
+        map.to_string()
+    }
+}
 
-        for next in possible {
-            if (self.goal)(&next) {
-                return Some(steps);
-            }
-            let cont = match self.seen.entry(next) {
-                Entry::Occupied(mut e) => {
-                    let x = e.get_mut();
-                    if steps < *x {
-                        // we found a shorter route
-                        *x = steps;
-                        true
-                    } else {
-                        // we already got to this point quicker another way
-                        false
-                    }
-                }
-                Entry::Vacant(e) => {
-                    // first time we got here!
-                    e.insert(steps);
-                    true
-                }
-            };
+pub struct Day12;
 
-            if cont {
-                remaining.push(self.find_path(&next, steps, dir));
-            }
-        }
+impl Solution for Day12 {
+    type Input = Map;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-        remaining.into_iter().flatten().min()
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Map::new(parse(input)?)?)
     }
-}
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let data = parse(input);
-    let map = Map::new(data);
-    let len = map.find_path_from_start();
+    fn part1(map: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(map.route_from_start()?.steps())
+    }
 
-    println!("len: {}", len);
-    Ok(())
+    fn part2(map: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(map.route_from_lowest()?.steps())
+    }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let data = parse(input);
-    let map = Map::new(data);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let len = Hike::hike(&map, &map.end, Direction::Down, |l| {
-        match map.height.get(l) {
-            Some(h) => *h == 'a' as u8,
-            None => false,
-        }
-    })
-    .unwrap();
-
-    println!("len: {}", len);
+    #[test]
+    fn test_example() {
+        let map = Day12::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day12::part1(&map).unwrap(), 31);
+        assert_eq!(Day12::part2(&map).unwrap(), 29);
+    }
 
-    Ok(())
+    #[test]
+    fn test_route() {
+        let map = Day12::parse(TEST_INPUT).unwrap();
+        let route = map.route_from_start().unwrap();
+        assert_eq!(route.path.first(), Some(&map.start));
+        assert_eq!(
+            map.render(&route),
+            "v..v<<<<
+>v.vv<<^
+.v.v>E^^
+.>v>>>^^
+..>>>>>^"
+        );
+
+        // the same length as searching forwards from every `a`
+        let lowest = map.route_from_lowest().unwrap();
+        assert_eq!(map.height[lowest.path[0]], b'a');
+        assert_eq!(*lowest.path.last().unwrap(), map.end);
+        let starts = map.height.points().filter(|&p| map.height[p] == b'a');
+        let forwards = map.search(starts, |p| p == map.end, |a, b| map.can_step(a, b));
+        assert_eq!(forwards.unwrap().steps(), lowest.steps());
+
+        let map = Day12::parse("SbcdE").unwrap();
+        assert_eq!(
+            map.route_from_start(),
+            Err("no route from S to E".to_owned())
+        );
+        assert_eq!(
+            map.route_from_lowest(),
+            Err("no route from any a to E".to_owned())
+        );
+    }
 }
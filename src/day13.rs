@@ -1,9 +1,33 @@
-use std::{cmp::Ordering, error::Error, str::Chars};
+use std::{cmp::Ordering, error::Error, str::CharIndices, str::FromStr};
 
-use crate::util;
+use crate::{parse::ParseError, solution::Solution, util};
+
+static TEST_INPUT: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-enum Value {
+pub enum Value {
     Int(i32),
     List(Vec<Value>),
 }
@@ -22,9 +46,9 @@ impl Ord for Value {
                 // after the matched elements, compare the length
                 ll.len().cmp(&rl.len())
             }
-            (Value::Int(li), Value::Int(ri)) => li.cmp(&ri),
+            (Value::Int(li), Value::Int(ri)) => li.cmp(ri),
             (Value::List(_), Value::Int(_)) => self.cmp(&Value::List(vec![other.clone()])),
-            (Value::Int(_), Value::List(_)) => Value::List(vec![self.clone()]).cmp(&other),
+            (Value::Int(_), Value::List(_)) => Value::List(vec![self.clone()]).cmp(other),
         }
     }
 }
@@ -36,113 +60,173 @@ impl PartialOrd for Value {
 }
 
 struct Parser<'a> {
-    iter: Chars<'a>,
+    source: &'a str,
+    iter: CharIndices<'a>,
 }
 
 impl<'a> Parser<'a> {
-    fn parse(s: &'a str) -> Value {
-        let mut iter = s.chars();
+    fn parse(s: &'a str) -> Result<Value, ParseError> {
+        let mut parser = Self {
+            source: s,
+            iter: s.char_indices(),
+        };
         // get the first char, which should be a `[`
-        let first = iter.next();
-        if first != Some('[') {
-            panic!("Bad start: {:?}", first);
+        match parser.iter.next() {
+            Some((_, '[')) => {}
+            Some((i, c)) => return Err(parser.unexpected(i, c, "expected `[`")),
+            None => return Err(ParseError::missing(s, "expected `[`")),
         }
-        let mut parser = Self { iter };
-        parser.parse_list()
+        let value = parser.parse_list()?;
+        if let Some((i, c)) = parser.iter.next() {
+            return Err(parser.unexpected(i, c, "expected end of line"));
+        }
+        Ok(value)
+    }
+
+    fn unexpected(&self, i: usize, c: char, message: &str) -> ParseError {
+        ParseError::new(self.source, &self.source[i..i + c.len_utf8()], message)
+    }
+
+    fn ran_out(&self) -> ParseError {
+        ParseError::missing(self.source, "expected `]`")
     }
 
-    fn parse_int(&mut self, c: char) -> (Value, /* end bracket found */ bool) {
+    fn parse_int(
+        &mut self,
+        start: usize,
+        c: char,
+    ) -> Result<(Value, /* end bracket found */ bool), ParseError> {
         let mut v = c as i32 - '0' as i32;
-        while let Some(c) = self.iter.next() {
+        while let Some((i, c)) = self.iter.next() {
             match c {
-                c @ '0'..='9' => v = v * 10 + (c as i32 - '0' as i32),
+                c @ '0'..='9' => {
+                    v = v
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(c as i32 - '0' as i32))
+                        .ok_or_else(|| {
+                            let text = &self.source[start..=i];
+                            ParseError::new(self.source, text, "number is too big")
+                        })?
+                }
                 ',' => {
-                    return (Value::Int(v), false);
+                    return Ok((Value::Int(v), false));
                 }
                 ']' => {
-                    return (Value::Int(v), true);
+                    return Ok((Value::Int(v), true));
                 }
-                c => panic!("Unexpected in int: {:?}", c),
+                c => return Err(self.unexpected(i, c, "expected a digit, `,` or `]`")),
             }
         }
-        panic!("Ran out of items!");
+        Err(self.ran_out())
     }
 
-    fn parse_list(&mut self) -> Value {
+    fn parse_list(&mut self) -> Result<Value, ParseError> {
         let mut list = Vec::new();
-        while let Some(c) = self.iter.next() {
+        // a `,` has to be followed by another value
+        let mut after_comma = false;
+        while let Some((i, c)) = self.iter.next() {
             match c {
                 '[' => {
-                    list.push(self.parse_list());
+                    list.push(self.parse_list()?);
 
                     // expect a `,` or `]`
                     match self.iter.next() {
-                        Some(',') => {} // noop, just eat
-                        Some(']') => return Value::List(list),
-                        x => panic!("Unexpected after list item: {:?}", x),
+                        Some((_, ',')) => after_comma = true,
+                        Some((_, ']')) => return Ok(Value::List(list)),
+                        Some((i, c)) => return Err(self.unexpected(i, c, "expected `,` or `]`")),
+                        None => return Err(self.ran_out()),
                     }
                 }
-                ']' => return Value::List(list),
+                ']' if after_comma => return Err(self.unexpected(i, c, "expected `[` or a digit")),
+                ']' => return Ok(Value::List(list)),
                 c @ '0'..='9' => {
-                    let (i, done) = self.parse_int(c);
-                    list.push(i);
+                    let (v, done) = self.parse_int(i, c)?;
+                    list.push(v);
                     if done {
-                        return Value::List(list);
+                        return Ok(Value::List(list));
                     }
+                    after_comma = true;
                 }
-                c => panic!("Unexpected after list item: {:?}", c),
+                c => return Err(self.unexpected(i, c, "expected `[`, `]` or a digit")),
             }
         }
-        panic!("Ran out of items!");
+        Err(self.ran_out())
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut idx = 0;
-    let lines: Vec<_> = util::read_lines(input).collect();
+impl FromStr for Value {
+    type Err = ParseError;
 
-    let mut right = Vec::new();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::parse(s)
+    }
+}
 
-    while idx < lines.len() {
-        let l = Parser::parse(lines[idx]);
-        let r = Parser::parse(lines[idx + 1]);
+pub struct Day13;
 
-        // next pair
-        idx += 3;
+impl Solution for Day13 {
+    type Input = Vec<(Value, Value)>;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-        if l.cmp(&r) == Ordering::Less {
-            right.push(idx / 3);
-        }
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        // pairs of packets separated by blank lines
+        let pairs = util::blocks(input).map(|(offset, block)| {
+            let lines: Vec<_> = block.lines().collect();
+            let packet = |i: usize| {
+                lines[i]
+                    .parse()
+                    .map_err(|e: ParseError| e.at_line(offset + i + 1))
+            };
+            match lines.len() {
+                2 => Ok((packet(0)?, packet(1)?)),
+                1 => {
+                    let err = ParseError::missing(lines[0], "expected a second packet");
+                    Err(err.at_line(offset + 1))
+                }
+                _ => {
+                    let err = ParseError::new(lines[2], lines[2], "expected a blank line");
+                    Err(err.at_line(offset + 3))
+                }
+            }
+        });
+        Ok(pairs.collect::<Result<_, _>>()?)
     }
 
-    println!("{}", right.iter().sum::<usize>());
+    fn part1(pairs: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, (l, r))| l.cmp(r) == Ordering::Less)
+            .map(|(i, _)| i + 1)
+            .sum())
+    }
 
-    Ok(())
-}
+    fn part2(pairs: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let d1: Value = "[[2]]".parse()?;
+        let d2: Value = "[[6]]".parse()?;
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut lines: Vec<_> = util::read_lines(input).filter(|s| !s.is_empty()).collect();
-    lines.push("[[2]]");
-    lines.push("[[6]]");
-    let mut values: Vec<_> = lines.into_iter().map(|l| Parser::parse(l)).collect();
-    values.sort();
-
-    let d1 = Parser::parse("[[2]]");
-    let d2 = Parser::parse("[[6]]");
-
-    // find the dividers
-    let mut di1 = 0;
-    let mut di2 = 0;
-    for (i, v) in values.iter().enumerate() {
-        if *v == d1 {
-            di1 = i + 1;
-        }
-        if *v == d2 {
-            di2 = i + 1;
+        let mut values: Vec<Value> = pairs
+            .iter()
+            .flat_map(|(l, r)| [l.clone(), r.clone()])
+            .collect();
+        values.push(d1.clone());
+        values.push(d2.clone());
+        values.sort();
+
+        // find the dividers
+        let mut di1 = 0;
+        let mut di2 = 0;
+        for (i, v) in values.iter().enumerate() {
+            if *v == d1 {
+                di1 = i + 1;
+            }
+            if *v == d2 {
+                di2 = i + 1;
+            }
         }
+        Ok(di1 * di2)
     }
-    println!("{}", di1 * di2);
-    Ok(())
 }
 
 #[cfg(test)]
@@ -151,13 +235,41 @@ mod tests {
 
     #[test]
     fn test() {
-        let v = Parser::parse("[]");
+        let v = Parser::parse("[]").unwrap();
         assert_eq!(v, Value::List(vec![]));
     }
 
+    #[test]
+    fn test_parse_error() {
+        let err = Parser::parse("[1,[2,x]]").unwrap_err();
+        assert_eq!(err.column(), 7);
+        let err = Parser::parse("[1,[2,3]").unwrap_err();
+        assert_eq!(err.column(), 9);
+        let err = Parser::parse("[1,]").unwrap_err();
+        assert_eq!(err.column(), 4);
+        let err = Parser::parse("[[1],]").unwrap_err();
+        assert_eq!(err.column(), 6);
+        let err = Parser::parse("[1,99999999999]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "column 4: number is too big, found \"9999999999\""
+        );
+
+        let err = Day13::parse("[1]\n[2]\n[3]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: expected a blank line, found \"[3]\""
+        );
+        let err = Day13::parse("[1]\n[2]\n\n[3]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 4: expected a second packet, found end of line"
+        );
+    }
+
     #[test]
     fn test2() {
-        let v = Parser::parse("[1,[1,3],10,1]");
+        let v = Parser::parse("[1,[1,3],10,1]").unwrap();
         assert_eq!(
             v,
             Value::List(vec![
@@ -170,8 +282,8 @@ mod tests {
     }
 
     fn test_compare(l: &str, r: &str, expected: Ordering) {
-        let l = Parser::parse(l);
-        let r = Parser::parse(r);
+        let l = Parser::parse(l).unwrap();
+        let r = Parser::parse(r).unwrap();
         assert_eq!(expected, l.cmp(&r));
     }
 
@@ -203,4 +315,11 @@ mod tests {
             Ordering::Greater,
         );
     }
+
+    #[test]
+    fn test_example() {
+        let values = Day13::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day13::part1(&values).unwrap(), 13);
+        assert_eq!(Day13::part2(&values).unwrap(), 140);
+    }
 }
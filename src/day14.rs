@@ -1,6 +1,11 @@
-use std::{collections::HashMap, error::Error};
+use std::{error::Error, fmt::Display};
 
-use crate::util;
+use crate::{
+    grid::{Point, SparseGrid},
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
@@ -11,79 +16,82 @@ enum Space {
     Sand,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Loc(usize, usize);
-
-impl From<&str> for Loc {
-    fn from(value: &str) -> Self {
-        let bits: Vec<_> = value.split(',').collect();
-        Self(bits[0].parse().unwrap(), bits[1].parse().unwrap())
+impl Display for Space {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Space::Rock => write!(f, "#"),
+            Space::Sand => write!(f, "o"),
+        }
     }
 }
 
-#[derive(Debug)]
-struct Map {
-    spaces: HashMap<Loc, Space>,
-    maxy: usize,
+// e.g. 498,4
+fn parse_point(s: &str) -> Result<Point, ParseError> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| ParseError::new(s, s, "expected x,y"))?;
+    Ok(Point::new(parse::number(s, x)?, parse::number(s, y)?))
+}
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    spaces: SparseGrid<Space>,
+    maxy: i64,
     floor: bool,
 }
 
 impl Map {
-    fn parse(s: &str) -> Self {
-        let mut spaces = HashMap::new();
-
-        for line in util::read_lines(s) {
-            let pairs: Vec<_> = line.split(" -> ").map(|s| Loc::from(s)).collect();
-            for pair in pairs.windows(2) {
-                let l0 = pair[0];
-                let l1 = pair[1];
-
-                if l0.0 == l1.0 {
-                    // y value changing
-                    let (miny, maxy) = if l0.1 < l1.1 {
-                        (l0.1, l1.1)
-                    } else {
-                        (l1.1, l0.1)
-                    };
-                    for y in miny..=maxy {
-                        spaces.insert(Loc(l0.0, y), Space::Rock);
-                    }
-                } else if l0.1 == l1.1 {
-                    // x value changing
-                    let (minx, maxx) = if l0.0 < l1.0 {
-                        (l0.0, l1.0)
-                    } else {
-                        (l1.0, l0.0)
-                    };
-                    for x in minx..=maxx {
-                        spaces.insert(Loc(x, l0.1), Space::Rock);
-                    }
-                } else {
-                    panic!("bad pair: {:?}, {:?}", l0, l1);
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        let mut spaces = SparseGrid::new();
+
+        for (n, line) in util::numbered_lines(s) {
+            let at = |e: ParseError| e.at_line(n);
+            let parts: Vec<_> = line.split(" -> ").collect();
+            let points: Vec<_> = parts
+                .iter()
+                .map(|&p| parse_point(p).map_err(|e| e.within(line, p)))
+                .collect::<Result<_, _>>()
+                .map_err(at)?;
+            for (i, pair) in points.windows(2).enumerate() {
+                let (from, to) = (pair[0], pair[1]);
+                if from.x != to.x && from.y != to.y {
+                    let diagonal = ParseError::new(line, parts[i + 1], "expected a straight line");
+                    return Err(at(diagonal));
+                }
+
+                let step = Point::new((to.x - from.x).signum(), (to.y - from.y).signum());
+                let mut p = from;
+                spaces.insert(p, Space::Rock);
+                while p != to {
+                    p += step;
+                    spaces.insert(p, Space::Rock);
                 }
             }
         }
 
-        let maxy = spaces.keys().map(|l| l.1).max().unwrap();
+        let maxy = spaces
+            .bounds()
+            .map(|b| b.max.y)
+            .ok_or_else(|| ParseError::missing(s, "expected some rock"))?;
 
-        Self {
+        Ok(Self {
             spaces,
             maxy,
             floor: false,
-        }
+        })
     }
 
-    fn occupied(&self, loc: Loc) -> bool {
-        self.spaces.contains_key(&loc) || (self.floor && loc.1 == self.maxy + 2)
+    fn occupied(&self, loc: Point) -> bool {
+        self.spaces.contains(loc) || (self.floor && loc.y == self.maxy + 2)
     }
 
-    fn is_empty(&self, loc: Loc) -> bool {
+    fn is_empty(&self, loc: Point) -> bool {
         !self.occupied(loc)
     }
 
-    fn stopper(&self, start: Loc) -> Option<Loc> {
-        for y in start.1..=self.maxy + 2 {
-            let l = Loc(start.0, y);
+    fn stopper(&self, start: Point) -> Option<Point> {
+        for y in start.y..=self.maxy + 2 {
+            let l = Point::new(start.x, y);
             if !self.is_empty(l) {
                 return Some(l);
             }
@@ -91,66 +99,87 @@ impl Map {
         None
     }
 
-    fn find_rest(&self, start: Loc) -> Option<Loc> {
+    fn find_rest(&self, start: Point) -> Option<Point> {
         if let Some(s) = self.stopper(start) {
-            let left = Loc(s.0 - 1, s.1);
-            let right = Loc(s.0 + 1, s.1);
+            let left = s + Point::LEFT;
+            let right = s + Point::RIGHT;
 
-            if self.is_empty(left) {
-                return self.find_rest(left);
+            return if self.is_empty(left) {
+                self.find_rest(left)
             } else if self.is_empty(right) {
-                return self.find_rest(right);
+                self.find_rest(right)
             } else {
                 // we come to a stop above the stopper
-                return Some(Loc(s.0, s.1 - 1));
-            }
+                Some(s + Point::UP)
+            };
         }
         None
     }
 
-    fn sand(&mut self, loc: Loc) {
+    fn sand(&mut self, loc: Point) {
         self.spaces.insert(loc, Space::Sand);
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut map = Map::parse(input);
-    println!("{:?}", map);
-    let sand_start = Loc(500, 0);
-
-    let mut units = 0;
-    loop {
-        match map.find_rest(sand_start) {
-            Some(l) => {
-                map.sand(l);
-                units += 1;
-                println!("{:?}, {}", l, units);
-            }
-            None => break,
-        }
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.spaces)
     }
-    Ok(())
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut map = Map::parse(input);
-    map.floor = true;
-    println!("{:?}", map);
-    let sand_start = Loc(500, 0);
-
-    let mut units = 0;
-    loop {
-        match map.find_rest(sand_start) {
-            Some(l) => {
-                map.sand(l);
-                units += 1;
-                println!("{:?}, {}", l, units);
-                if l == sand_start {
-                    break;
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Map;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Map::parse(input)?)
+    }
+
+    fn part1(map: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let mut map = map.clone();
+        let sand_start = Point::new(500, 0);
+
+        let mut units = 0;
+        while let Some(l) = map.find_rest(sand_start) {
+            map.sand(l);
+            units += 1;
+        }
+        Ok(units)
+    }
+
+    fn part2(map: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let mut map = map.clone();
+        map.floor = true;
+        let sand_start = Point::new(500, 0);
+
+        let mut units = 0;
+        loop {
+            match map.find_rest(sand_start) {
+                Some(l) => {
+                    map.sand(l);
+                    units += 1;
+                    if l == sand_start {
+                        break;
+                    }
                 }
+                None => panic!("shouldn't happen!"),
             }
-            None => panic!("shouldn't happen!"),
         }
+        Ok(units)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let map = Day14::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day14::part1(&map).unwrap(), 24);
+        assert_eq!(Day14::part2(&map).unwrap(), 93);
     }
-    Ok(())
 }
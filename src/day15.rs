@@ -1,6 +1,12 @@
-use std::{collections::HashMap, error::Error};
+use std::{error::Error, str::FromStr};
 
-use crate::util;
+use crate::{
+    grid::{Bounds, Point},
+    interval::{Interval, IntervalSet},
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
@@ -18,196 +24,244 @@ Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3
 ";
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-struct Loc(i32, i32);
-
-impl Loc {
-    fn manhattan_distance(&self, other: Loc) -> i32 {
-        (self.0 - other.0).abs() + (self.1 - other.1).abs()
-    }
+// e.g. y=1: is the 3rd token of `line`
+fn parse_assign(line: &str, n: usize, name: &str) -> Result<i64, ParseError> {
+    let s = parse::token(line, n, name)?;
+    let s = s.trim_end_matches([':', ',']);
+    parse::number(line, parse::after(line, s, name)?)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-enum Space {
-    Beacon,
-    Sensor,
-    Empty,
+#[derive(Debug, Clone)]
+struct Pair {
+    sensor: Point,
+    beacon: Point,
+    dist: i64,
 }
 
-// e.g. y=1:
-fn parse_assign(s: &str) -> i32 {
-    let s = match s.strip_suffix(':') {
-        Some(s) => s,
-        None => s,
-    };
-    let s = match s.strip_suffix(',') {
-        Some(s) => s,
-        None => s,
-    };
-    let blah: Vec<_> = s.split('=').collect();
-    blah[1].parse().expect("bad int")
+// Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+impl FromStr for Pair {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let sensor = Point::new(parse_assign(line, 2, "x=")?, parse_assign(line, 3, "y=")?);
+        let beacon = Point::new(parse_assign(line, 8, "x=")?, parse_assign(line, 9, "y=")?);
+
+        Ok(Pair {
+            sensor,
+            beacon,
+            dist: sensor.manhattan(beacon),
+        })
+    }
 }
 
-#[derive(Debug)]
-struct Pair {
-    sensor: Loc,
-    beacon: Loc,
-    dist: i32,
+impl Pair {
+    // the part of the row the sensor can see, if any
+    fn coverage(&self, row: i64) -> Option<Interval> {
+        let width = self.dist - (self.sensor.y - row).abs();
+        (width >= 0).then(|| Interval::new(self.sensor.x - width, self.sensor.x + width))
+    }
 }
 
-#[derive(Debug)]
-struct Map {
-    spaces: HashMap<Loc, Space>,
+#[derive(Debug, Clone)]
+pub struct Map {
     pairs: Vec<Pair>,
 }
 
 impl Map {
-    fn parse(s: &str) -> Self {
-        let mut spaces = HashMap::new();
-        let mut pairs = Vec::new();
-        for line in util::read_lines(s) {
-            let blah: Vec<_> = line.split_ascii_whitespace().collect();
-            let sx = parse_assign(blah[2]);
-            let sy = parse_assign(blah[3]);
-            let bx = parse_assign(blah[8]);
-            let by = parse_assign(blah[9]);
-
-            let sensor = Loc(sx, sy);
-            let beacon = Loc(bx, by);
-
-            pairs.push(Pair {
-                sensor,
-                beacon,
-                dist: sensor.manhattan_distance(beacon),
-            });
-
-            spaces.insert(sensor, Space::Sensor);
-            spaces.insert(beacon, Space::Beacon);
-        }
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            pairs: util::parse_lines(s)?,
+        })
+    }
 
-        Self { spaces, pairs }
+    // everything in the row seen by at least one sensor
+    fn coverage(&self, row: i64) -> IntervalSet {
+        self.pairs.iter().filter_map(|p| p.coverage(row)).collect()
     }
 
-    fn fill_empty(&mut self) {
-        for pair in self.pairs.iter() {
-            let dist = pair.dist;
+    // the number of positions in the row where a beacon cannot be
+    fn non_beacon(&self, row: i64) -> i64 {
+        let coverage = self.coverage(row);
+        let mut beacons: Vec<_> = self
+            .pairs
+            .iter()
+            .map(|p| p.beacon)
+            .filter(|b| b.y == row && coverage.contains(b.x))
+            .collect();
+        beacons.sort();
+        beacons.dedup();
 
-            for dx in -dist..=dist {
-                let disty = dist - dx.abs();
-                for dy in -disty..=disty {
-                    let l = Loc(pair.sensor.0 + dx, pair.sensor.1 + dy);
-                    self.spaces.entry(l).or_insert(Space::Empty);
-                }
-            }
-        }
+        coverage.coverage() - beacons.len() as i64
     }
 
-    fn fill_empty_row(&mut self, row: i32) {
-        for pair in self.pairs.iter() {
-            let dist = pair.dist;
-            let dy = (pair.sensor.1 - row).abs();
-            println!(
-                "sensor: {:?}, beacon: {:?}, dist: {}, dy: {}",
-                pair.sensor, pair.beacon, dist, dy
-            );
-            if dy > dist {
-                continue;
-            }
+    // the uncovered points in `area` of `row`
+    fn gaps(&self, row: i64, area: Bounds) -> impl Iterator<Item = Point> {
+        let within = Interval::new(area.min.x, area.max.x);
+        let gaps = self.coverage(row).gaps(within);
+        let gaps: Vec<_> = gaps.iter().copied().collect();
+        gaps.into_iter()
+            .flat_map(move |gap| (gap.lo..=gap.hi).map(move |x| Point::new(x, row)))
+    }
 
-            let distx = dist - dy;
+    // every point in `area` no sensor can see. in rotated coordinates
+    // u = x + y and v = x - y each sensor's diamond has straight edges, so
+    // an uncovered region can only start, end or change shape on a row
+    // where the lines just outside two diamonds cross or one of them meets
+    // the edge of the area. those rows are checked, and the rows between
+    // two of them are only checked one by one if one of them has a gap.
+    fn uncovered(&self, area: Bounds) -> Vec<Point> {
+        let mut us = Vec::new();
+        let mut vs = Vec::new();
+        for pair in self.pairs.iter() {
+            let (u, v) = (pair.sensor.x + pair.sensor.y, pair.sensor.x - pair.sensor.y);
+            us.extend([u - pair.dist - 1, u + pair.dist + 1]);
+            vs.extend([v - pair.dist - 1, v + pair.dist + 1]);
+        }
 
-            for dx in -distx..=distx {
-                let l = Loc(pair.sensor.0 + dx, row);
-                self.spaces.entry(l).or_insert(Space::Empty);
+        let Bounds { min, max } = area;
+        let mut rows = vec![min.y, max.y];
+        for &u in us.iter() {
+            for &v in vs.iter() {
+                // lines of different parity cross between rows
+                rows.push((u - v).div_euclid(2));
             }
+            rows.extend([u - min.x, u - max.x]);
         }
-    }
-
-    // only one spot for a beacon
-    // therefore it must lie at manhatten distance + 1 to
-    // one or more sensors
-    fn possible(&self, min: i32, max: i32) -> HashMap<Loc, usize> {
-        let mut possible = HashMap::new();
+        for &v in vs.iter() {
+            rows.extend([min.x - v, max.x - v]);
+        }
+        // the rows either side too, for crossings between cells
+        let mut rows: Vec<i64> = rows
+            .into_iter()
+            .flat_map(|y| [y - 1, y, y + 1])
+            .filter(|y| (min.y..=max.y).contains(y))
+            .collect();
+        rows.sort();
+        rows.dedup();
 
-        for pair in self.pairs.iter() {
-            let dist = pair.dist + 1;
-            for dx in -dist..=dist {
-                let disty = dist - dx.abs();
-                let x = pair.sensor.0 + dx;
-                if x >= min && x <= max {
-                    let mut blah = Vec::new();
-                    if disty == 0 {
-                        blah.push(Loc(x, pair.sensor.1));
-                    } else {
-                        let miny = pair.sensor.1 - disty;
-                        if miny >= min && miny <= max {
-                            blah.push(Loc(x, miny));
-                        }
-                        let maxy = pair.sensor.1 + disty;
-                        if maxy >= min && maxy <= max {
-                            blah.push(Loc(x, maxy));
-                        }
-                    }
-
-                    for p in blah {
-                        possible
-                            .entry(p)
-                            .and_modify(|count| *count += 1)
-                            .or_insert(1);
-                    }
+        let mut points = Vec::new();
+        for (i, &row) in rows.iter().enumerate() {
+            points.extend(self.gaps(row, area));
+            let Some(&next) = rows.get(i + 1) else {
+                continue;
+            };
+            if next > row + 1 && self.gaps(row + 1, area).next().is_some() {
+                for y in row + 1..next {
+                    points.extend(self.gaps(y, area));
                 }
             }
         }
-
-        possible
+        points
     }
 
-    fn is_outside(&self, l: Loc) -> bool {
-        for p in self.pairs.iter() {
-            if l.manhattan_distance(p.sensor) <= p.dist {
-                return false;
+    fn tuning_frequency(&self, max: i64) -> Result<i64, String> {
+        let area = Bounds::new(Point::ORIGIN, Point::new(max, max));
+        match self.uncovered(area)[..] {
+            [p] => Ok(p.x * 4000000 + p.y),
+            [] => Err("no position for the distress beacon".to_owned()),
+            ref points => {
+                let points: Vec<_> = points
+                    .iter()
+                    .map(|p| format!("({}, {})", p.x, p.y))
+                    .collect();
+                Err(format!(
+                    "the distress beacon's position is ambiguous, it could be at any of {}",
+                    points.join(", ")
+                ))
             }
         }
-        true
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut map = Map::parse(input);
+pub struct Day15;
 
-    // let row = 10;
-    let row = 2000000;
-    map.fill_empty_row(row);
+impl Solution for Day15 {
+    type Input = Map;
+    type Answer1 = i64;
+    type Answer2 = i64;
 
-    let non_beacon = map
-        .spaces
-        .iter()
-        .filter(|(&l, &s)| l.1 == row && s != Space::Beacon)
-        .count();
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Map::parse(input)?)
+    }
+
+    fn part1(map: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(map.non_beacon(2000000))
+    }
 
-    println!("non beacon: {}", non_beacon);
-    Ok(())
+    fn part2(map: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(map.tuning_frequency(4000000)?)
+    }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    // let map = Map::parse(TEST_INPUT);
-    // let pos = map.possible(0, 20);
-    let map = Map::parse(input);
-    let pos = map.possible(0, 4000000);
-
-    // sort by number of overlaps decending.
-    // the point we're looking for should be on the edge of many ranges
-    let mut pos: Vec<_> = pos.iter().collect();
-    pos.sort_by(|x, y| y.1.cmp(x.1));
-
-    // go through the points and do a real test
-    for (p, _) in pos {
-        if map.is_outside(*p) {
-            println!("soln: {:?}", p);
-            let freq = p.0 as i64 * 4000000 + p.1 as i64;
-            println!("freq: {}", freq);
-            break;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covered(map: &Map, p: Point) -> bool {
+        map.pairs
+            .iter()
+            .any(|pair| p.manhattan(pair.sensor) <= pair.dist)
+    }
+
+    #[test]
+    fn test_example() {
+        let map = Day15::parse(TEST_INPUT).unwrap();
+        assert_eq!(map.non_beacon(10), 26);
+        assert_eq!(map.tuning_frequency(20), Ok(56000011));
+    }
+
+    #[test]
+    fn test_uncovered() {
+        let map = Day15::parse(TEST_INPUT).unwrap();
+        let area = Bounds::new(Point::ORIGIN, Point::new(20, 20));
+        assert_eq!(map.uncovered(area), [Point::new(14, 11)]);
+
+        // a small diamond in the middle leaves the four corners
+        let map = Day15::parse("Sensor at x=1, y=1: closest beacon is at x=1, y=0").unwrap();
+        let area = Bounds::new(Point::ORIGIN, Point::new(2, 2));
+        let corners = [(0, 0), (2, 0), (0, 2), (2, 2)].map(|(x, y)| Point::new(x, y));
+        assert_eq!(map.uncovered(area), corners);
+        assert_eq!(
+            map.tuning_frequency(2),
+            Err("the distress beacon's position is ambiguous, \
+                it could be at any of (0, 0), (2, 0), (0, 2), (2, 2)"
+                .to_owned())
+        );
+
+        // every uncovered cell, checked against each cell in turn
+        let map = Day15::parse("Sensor at x=5, y=5: closest beacon is at x=5, y=4").unwrap();
+        let area = Bounds::new(Point::ORIGIN, Point::new(30, 20));
+        let mut expected: Vec<_> = area.points().filter(|&p| !covered(&map, p)).collect();
+        expected.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(expected.len(), 30 * 20 + 51 - 5);
+        assert_eq!(map.uncovered(area), expected);
+
+        // and on some scattered sensors
+        let mut seed = 7u64;
+        let mut next = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i64 % n as i64
+        };
+        for _ in 0..50 {
+            let lines: Vec<_> = (0..4)
+                .map(|_| {
+                    let (x, y) = (next(30), next(30));
+                    format!(
+                        "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                        x,
+                        y,
+                        x + next(9) - 4,
+                        y + next(9) - 4
+                    )
+                })
+                .collect();
+            let map = Day15::parse(&lines.join("\n")).unwrap();
+            let area = Bounds::new(Point::new(2, 3), Point::new(25, 27));
+            let mut expected: Vec<_> = area.points().filter(|&p| !covered(&map, p)).collect();
+            expected.sort_by_key(|p| (p.y, p.x));
+            assert_eq!(map.uncovered(area), expected);
         }
     }
-    Ok(())
 }
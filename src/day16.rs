@@ -1,9 +1,11 @@
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-};
+use std::{cmp::Reverse, collections::HashMap, error::Error};
 
-use crate::{graph::Graph, util};
+use crate::{
+    graph::{Distances, Graph, NodeId},
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
 Valve BB has flow rate=13; tunnels lead to valves CC, AA
@@ -16,189 +18,173 @@ Valve HH has flow rate=22; tunnel leads to valve GG
 Valve II has flow rate=0; tunnels lead to valves AA, JJ
 Valve JJ has flow rate=21; tunnel leads to valve II";
 
-fn parse_line(s: &str) -> (&str, usize, Vec<&str>) {
-    let blah: Vec<_> = s.split(" ").collect();
-    let name = blah[1];
-    let rate: Vec<_> = blah[4].split("=").collect();
-    let rate = rate[1].strip_suffix(';').unwrap().parse().unwrap();
-    let next = blah[9..]
-        .iter()
-        .map(|x| match x.strip_suffix(',') {
-            None => x,
-            Some(x) => x,
-        })
+// Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+fn parse_line(s: &str) -> Result<(&str, usize, Vec<&str>), ParseError> {
+    let name = parse::token(s, 1, "a valve name")?;
+    let rate = parse::token(s, 4, "a flow rate")?;
+    let rate = parse::after(s, rate, "rate=")?;
+    let rate = rate
+        .strip_suffix(';')
+        .ok_or_else(|| ParseError::new(s, rate, "expected `;` after the rate"))?;
+    let rate = parse::number(s, rate)?;
+    let next: Vec<_> = s
+        .split_ascii_whitespace()
+        .skip(9)
+        .map(|x| x.strip_suffix(',').unwrap_or(x))
         .collect();
-    (name, rate, next)
-}
-
-#[derive(Clone, Debug)]
-struct Flow {
-    open: HashSet<String>,
-    flow: Vec<(/* rate */ usize, /* minute */ usize)>,
-}
-
-impl Flow {
-    fn new() -> Self {
-        Self {
-            open: HashSet::new(),
-            flow: Vec::new(),
-        }
-    }
-
-    fn open(&mut self, valve: &str, rate: usize, minute: usize) {
-        self.open.insert(valve.to_owned());
-        self.flow.push((rate, minute))
-    }
-
-    fn is_open(&self, valve: &str) -> bool {
-        self.open.contains(valve)
-    }
-
-    fn total(&self, minutes: usize) -> usize {
-        self.flow
-            .iter()
-            .map(|(rate, minute)| rate * (minutes - minute + 1))
-            .sum()
+    if next.is_empty() {
+        return Err(ParseError::missing(s, "expected a list of valves"));
     }
+    Ok((name, rate, next))
 }
 
-struct Volcano {
-    rates: HashMap<String, usize>,
-    graph: Graph<String>,
+pub struct Volcano {
+    tunnels: Graph<String>,
+    // the valves worth opening, each one's index is its bit in a mask
+    valves: Vec<NodeId>,
+    rates: Vec<usize>,
+    distances: Distances,
 }
 
 impl Volcano {
-    fn new(s: &str) -> Self {
-        let mut rates = HashMap::new();
-        let mut g1 = Graph::new();
-
-        for (from, rate, tos) in util::read_lines(s).map(|s| parse_line(s)) {
+    fn new(s: &str) -> Result<Self, ParseError> {
+        let mut tunnels = Graph::new();
+        let mut valves = Vec::new();
+        let mut rates = Vec::new();
+
+        for (n, line) in util::numbered_lines(s) {
+            let (from, rate, tos) = parse_line(line).map_err(|e| e.at_line(n))?;
+            let id = tunnels.add_node(from.to_owned());
             for to in tos {
-                g1.add_bidirectional_edge(from.to_owned(), to.to_owned(), 1);
+                tunnels.add_bidirectional_edge(from.to_owned(), to.to_owned(), 1);
             }
-            rates.insert(from.to_owned(), rate);
-        }
-
-        // we are only interested in AA (the start point) and valves with rate > 0
-        let mut interesting: Vec<_> = rates
-            .iter()
-            .filter_map(|(value, rate)| {
-                if *rate > 0 {
-                    Some(value.to_owned())
-                } else {
-                    None
-                }
-            })
-            .collect();
-        interesting.push("AA".to_owned());
-
-        // get the shortest paths between the points of interest
-        // and make a new graph
-        let mut graph = Graph::new();
-        for i in 0..interesting.len() - 1 {
-            for j in i + 1..interesting.len() {
-                let v1 = &interesting[i];
-                let v2 = &interesting[j];
-                if let Some(x) = g1.shortest_path(v1, v2) {
-                    graph.add_bidirectional_edge(v1.to_owned(), v2.to_owned(), x);
+            // stuck valves aren't worth the minute it takes to open them
+            if rate > 0 {
+                if valves.len() == u64::BITS as usize {
+                    let err = ParseError::new(line, from, "expected at most 64 working valves");
+                    return Err(err.at_line(n));
                 }
+                valves.push(id);
+                rates.push(rate);
             }
         }
 
-        Self { rates, graph }
+        let distances = tunnels.all_pairs();
+        Ok(Self {
+            tunnels,
+            valves,
+            rates,
+            distances,
+        })
     }
 
-    fn paths(&self, start: &String, minute: usize, flow: Flow, minutes: usize) -> Vec<Flow> {
-        let mut flows = Vec::new();
-
-        let next: Vec<_> = self
-            .graph
-            .next(start)
-            .iter()
-            .map(|e| (self.graph.node_data(&e.to), e.cost))
-            .filter(|&(valve, cost)| {
-                // we only want to move to a valve if it is:
-                // - not open
-                // - we have time to open it
-                // - it has a non-zero rate
-                !flow.is_open(valve)
-                    && minute + cost + 1 <= minutes
-                    && self.rates[&valve.to_owned()] > 0
-            })
-            .collect();
-
-        if next.is_empty() {
-            return vec![flow];
+    // the most pressure one agent can release by opening each set of
+    // valves, with the time left after reaching `at`
+    fn visit(
+        &self,
+        at: NodeId,
+        left: usize,
+        opened: u64,
+        pressure: usize,
+        best: &mut HashMap<u64, usize>,
+    ) {
+        let b = best.entry(opened).or_default();
+        *b = pressure.max(*b);
+
+        for (i, &valve) in self.valves.iter().enumerate() {
+            let bit = 1 << i;
+            if opened & bit != 0 {
+                continue;
+            }
+            // a valve opened in the last minute doesn't release anything
+            let Some(dist) = self.distances.get(at, valve).filter(|d| d + 1 < left) else {
+                continue;
+            };
+
+            let left = left - dist - 1;
+            let pressure = pressure + self.rates[i] * left;
+            self.visit(valve, left, opened | bit, pressure, best);
         }
+    }
 
-        for (valve, cost) in next {
-            let next_minute = minute + cost + 1;
-            // println!(
-            //     "{} -> {}: cost: {}, minutes: {} -> remaining: {}",
-            //     start, valve, cost, minutes, remaining
-            // );
+    // the best pressure for every set of valves one agent could open
+    fn best_per_subset(&self, start: &str, minutes: usize) -> Result<HashMap<u64, usize>, String> {
+        let start = self
+            .tunnels
+            .node_id(&start.to_owned())
+            .ok_or_else(|| format!("there's no valve {}", start))?;
+        let mut best = HashMap::new();
+        self.visit(start, minutes, 0, 0, &mut best);
+        Ok(best)
+    }
 
-            let mut flow = flow.clone();
-            flow.open(valve, self.rates[&valve.to_owned()], next_minute);
+    // the most pressure `agents` working together from `start` can release
+    // in the time. they never need to open the same valve, so each opens
+    // its own set of valves and the sets are combined
+    pub fn most_pressure(
+        &self,
+        start: &str,
+        minutes: usize,
+        agents: usize,
+    ) -> Result<usize, String> {
+        let single = self.best_per_subset(start, minutes)?;
+        if agents == 0 {
+            return Ok(0);
+        }
 
-            flows.append(&mut self.paths(valve, next_minute, flow, minutes))
+        let mut team = single.clone();
+        for _ in 2..agents {
+            team = combine(&team, &single);
+        }
+        if agents == 1 {
+            return Ok(team.into_values().max().unwrap_or(0));
         }
 
-        flows
+        // the last agent only needs the best partner for each team set,
+        // which is the first disjoint one going from the most pressure down
+        let mut last: Vec<_> = single.into_iter().collect();
+        last.sort_unstable_by_key(|&(_, pressure)| Reverse(pressure));
+        let team: Vec<_> = team.into_iter().collect();
+        let best = util::par_map(&team, |&(mask, pressure)| {
+            last.iter()
+                .find(|&&(other, _)| mask & other == 0)
+                .map_or(pressure, |&(_, p)| pressure + p)
+        });
+        Ok(best.into_iter().max().unwrap_or(0))
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let volcano = Volcano::new(input);
-
-    // volcano.graph.dump();
-
-    let start = "AA".to_owned();
-
-    let flows = volcano.paths(&start, 1, Flow::new(), 30);
-
-    // for flow in flows.iter() {
-    //     println!("{:?}: {}", flow, flow.total());
-    // }
-
-    println!("flows: {}", flows.len());
-
-    let best = flows.iter().map(|f| f.total(30)).max();
-
-    println!("{:?}", best);
-
-    Ok(())
+// the best pressure for every union of a disjoint set from each
+fn combine(a: &HashMap<u64, usize>, b: &HashMap<u64, usize>) -> HashMap<u64, usize> {
+    let mut combined = HashMap::new();
+    for (&m1, &p1) in a.iter() {
+        for (&m2, &p2) in b.iter().filter(|&(&m2, _)| m1 & m2 == 0) {
+            let best = combined.entry(m1 | m2).or_default();
+            *best = (p1 + p2).max(*best);
+        }
+    }
+    combined
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let volcano = Volcano::new(input);
-    let start = "AA".to_owned();
-
-    // works out routes for me
-    let flows = volcano.paths(&start, 1, Flow::new(), 26);
-
-    println!("{} flows", flows.len());
+pub struct Day16;
 
-    let mut best = 0;
+impl Solution for Day16 {
+    type Input = Volcano;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    // work out for elephant
-    for (i, flow) in flows.iter().enumerate() {
-        if i % 1000 == 0 {
-            println!("flow #{}", i);
-        }
-        let with_elephant = volcano.paths(&start, 1, flow.clone(), 26);
-
-        let el_best = with_elephant.iter().map(|f| f.total(26)).max().unwrap();
-
-        if el_best > best {
-            best = el_best;
-            println!("New best: {}", best);
-        }
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Volcano::new(input)?)
     }
 
-    println!("best: {}", best);
+    fn part1(volcano: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(volcano.most_pressure("AA", 30, 1)?)
+    }
 
-    Ok(())
+    fn part2(volcano: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        // me and an elephant, after spending 4 minutes teaching it
+        Ok(volcano.most_pressure("AA", 26, 2)?)
+    }
 }
 
 #[cfg(test)]
@@ -208,6 +194,31 @@ mod tests {
     #[test]
     fn test() {
         let line = "Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE";
-        assert_eq!(parse_line(line), ("DD", 20, vec!["CC", "AA", "EE"]));
+        assert_eq!(parse_line(line), Ok(("DD", 20, vec!["CC", "AA", "EE"])));
+    }
+
+    #[test]
+    fn test_example() {
+        let volcano = Day16::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day16::part1(&volcano).unwrap(), 1651);
+        assert_eq!(Day16::part2(&volcano).unwrap(), 1707);
+    }
+
+    #[test]
+    fn test_most_pressure() {
+        let volcano = Day16::parse(TEST_INPUT).unwrap();
+        // with six working valves more agents stop helping at some point
+        let pressures: Vec<_> = (0..5)
+            .map(|agents| volcano.most_pressure("AA", 26, agents).unwrap())
+            .collect();
+        assert!(
+            pressures.windows(2).all(|w| w[0] <= w[1]),
+            "{:?}",
+            pressures
+        );
+        assert_eq!(pressures[..3], [0, 1327, 1707]);
+        // starting next to the best valve
+        assert!(volcano.most_pressure("JJ", 30, 1).unwrap() >= 1651);
+        assert!(volcano.most_pressure("ZZ", 30, 1).is_err());
     }
 }
@@ -1,42 +1,36 @@
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-    iter::{Cycle, Enumerate},
-    str::Chars,
-};
+use std::{collections::HashSet, error::Error};
+
+use crate::{cycle, grid::Point, parse::ParseError, solution::Solution};
 
 static TEST_INPUT: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-struct Loc(usize, usize);
+// y grows up the chamber here, so falling is the opposite of Point::DOWN
+const FALL: Point = Point::UP;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Dir {
+pub enum Dir {
     Left,
     Right,
 }
 
-struct Pattern<'a> {
-    chars: Cycle<Enumerate<Chars<'a>>>,
+// the jets repeat forever
+struct Pattern {
+    jets: Vec<Dir>,
+    next: usize,
 }
 
-impl<'a> Pattern<'a> {
-    fn new(s: &'a str) -> Self {
+impl Pattern {
+    fn new(jets: &[Dir]) -> Self {
         Self {
-            chars: s.chars().enumerate().cycle(),
+            jets: jets.to_vec(),
+            next: 0,
         }
     }
 
-    fn next(&mut self) -> (usize, Dir) {
-        if let Some((i, c)) = self.chars.next() {
-            let dir = match c {
-                '>' => Dir::Right,
-                '<' => Dir::Left,
-                c => panic!("bad direction: {:?}", c),
-            };
-            return (i, dir);
-        }
-        unreachable!()
+    fn next(&mut self) -> Dir {
+        let dir = self.jets[self.next];
+        self.next = (self.next + 1) % self.jets.len();
+        dir
     }
 }
 
@@ -58,125 +52,75 @@ static SHAPE_KINDS: [ShapeKind; 5] = [
 ];
 
 struct ShapeGenerator {
-    shape_kinds: Cycle<std::slice::Iter<'static, ShapeKind>>,
+    next: usize,
 }
 
 impl ShapeGenerator {
     fn new() -> Self {
-        Self {
-            shape_kinds: SHAPE_KINDS.iter().cycle(),
-        }
+        Self { next: 0 }
     }
 
     fn next_shape(&mut self, height: usize) -> Shape {
-        let kind = *self.shape_kinds.next().unwrap();
-        match kind {
-            ShapeKind::Dash => Shape::new(
-                kind,
-                vec![
-                    Loc(2, height),
-                    Loc(3, height),
-                    Loc(4, height),
-                    Loc(5, height),
-                ],
-            ),
-            ShapeKind::Plus => Shape::new(
-                kind,
-                vec![
-                    Loc(3, height),
-                    Loc(2, height + 1),
-                    Loc(3, height + 1),
-                    Loc(4, height + 1),
-                    Loc(3, height + 2),
-                ],
-            ),
-            ShapeKind::L => Shape::new(
-                kind,
-                vec![
-                    Loc(2, height),
-                    Loc(3, height),
-                    Loc(4, height),
-                    Loc(4, height + 1),
-                    Loc(4, height + 2),
-                ],
-            ),
-            ShapeKind::Column => Shape::new(
-                kind,
-                vec![
-                    Loc(2, height),
-                    Loc(2, height + 1),
-                    Loc(2, height + 2),
-                    Loc(2, height + 3),
-                ],
-            ),
-            ShapeKind::Box => Shape::new(
-                kind,
-                vec![
-                    Loc(2, height),
-                    Loc(3, height),
-                    Loc(2, height + 1),
-                    Loc(3, height + 1),
-                ],
-            ),
-        }
+        let kind = SHAPE_KINDS[self.next];
+        self.next = (self.next + 1) % SHAPE_KINDS.len();
+        // shapes start two from the left wall
+        let corner = Point::new(2, height as i64);
+        Shape::new(
+            kind,
+            kind.offsets().into_iter().map(|o| corner + o).collect(),
+        )
+    }
+}
+
+impl ShapeKind {
+    // from the bottom left corner of the shape
+    fn offsets(&self) -> Vec<Point> {
+        let offsets: &[(i64, i64)] = match self {
+            ShapeKind::Dash => &[(0, 0), (1, 0), (2, 0), (3, 0)],
+            ShapeKind::Plus => &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
+            ShapeKind::L => &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
+            ShapeKind::Column => &[(0, 0), (0, 1), (0, 2), (0, 3)],
+            ShapeKind::Box => &[(0, 0), (1, 0), (0, 1), (1, 1)],
+        };
+        offsets.iter().map(|&(x, y)| Point::new(x, y)).collect()
     }
 }
 
 #[derive(Debug)]
 struct Shape {
     kind: ShapeKind,
-    locs: Vec<Loc>,
+    locs: Vec<Point>,
 }
 
 impl Shape {
-    fn new(kind: ShapeKind, locs: Vec<Loc>) -> Self {
+    fn new(kind: ShapeKind, locs: Vec<Point>) -> Self {
         Self { kind, locs }
     }
 
-    fn can_jet(&self, dir: Dir, chamber: &Chamber) -> bool {
-        for l in self.locs.iter() {
-            match dir {
-                Dir::Left => {
-                    if l.0 == 0 || chamber.occ.contains(&Loc(l.0 - 1, l.1)) {
-                        return false;
-                    }
-                }
-                Dir::Right => {
-                    if l.0 == 6 || chamber.occ.contains(&Loc(l.0 + 1, l.1)) {
-                        return false;
-                    }
-                }
-            }
-        }
-
-        true
+    fn can_move(&self, step: Point, chamber: &Chamber) -> bool {
+        self.locs
+            .iter()
+            .map(|&l| l + step)
+            .all(|l| (0..7).contains(&l.x) && l.y >= 0 && !chamber.occ.contains(&l))
     }
 
     fn jet(&mut self, dir: Dir, chamber: &Chamber) {
-        if self.can_jet(dir, chamber) {
+        let step = match dir {
+            Dir::Left => Point::LEFT,
+            Dir::Right => Point::RIGHT,
+        };
+        if self.can_move(step, chamber) {
             for l in self.locs.iter_mut() {
-                l.0 = match dir {
-                    Dir::Left => l.0 - 1,
-                    Dir::Right => l.0 + 1,
-                }
-            }
-        }
-    }
-
-    fn can_fall(&self, chamber: &Chamber) -> bool {
-        for l in self.locs.iter() {
-            if l.1 == 0 || chamber.occ.contains(&Loc(l.0, l.1 - 1)) {
-                return false;
+                *l += step;
             }
         }
-        true
     }
 
     // Returns true if the shape fell
     fn fall(&mut self, chamber: &Chamber) -> bool {
-        if self.can_fall(chamber) {
+        if self.can_move(FALL, chamber) {
             for l in self.locs.iter_mut() {
-                l.1 -= 1;
+                *l += FALL;
             }
             true
         } else {
@@ -186,7 +130,7 @@ impl Shape {
 }
 
 struct Chamber {
-    occ: HashSet<Loc>,
+    occ: HashSet<Point>,
     height: usize,
     tops: [usize; 7],
 }
@@ -206,9 +150,10 @@ impl Chamber {
 
     fn add_shape(&mut self, shape: Shape) {
         for l in shape.locs.iter() {
-            let y = l.1 + 1;
-            if y > self.tops[l.0] {
-                self.tops[l.0] = y;
+            let y = l.y as usize + 1;
+            let top = &mut self.tops[l.x as usize];
+            if y > *top {
+                *top = y;
             }
             if y > self.height {
                 self.height = y;
@@ -224,7 +169,7 @@ impl Chamber {
             let row = self.height + 1 - r;
             print!("|");
             for col in 0..=6 {
-                let c = if self.occ.contains(&Loc(col, row)) {
+                let c = if self.occ.contains(&Point::new(col, row as i64)) {
                     "#"
                 } else {
                     "."
@@ -237,110 +182,116 @@ impl Chamber {
     }
 }
 
-fn rounds(input: &str, r: usize, inc: usize) -> Chamber {
-    let mut pattern = Pattern::new(input);
-    let mut shape_generator = ShapeGenerator::new();
-    let mut chamber = Chamber::new();
+struct Simulation {
+    pattern: Pattern,
+    shapes: ShapeGenerator,
+    chamber: Chamber,
+}
+
+impl Simulation {
+    fn new(jets: &[Dir]) -> Self {
+        Self {
+            pattern: Pattern::new(jets),
+            shapes: ShapeGenerator::new(),
+            chamber: Chamber::new(),
+        }
+    }
 
-    for i in 0..r {
-        let mut shape = shape_generator.next_shape(chamber.height + 3);
+    fn drop_rock(&mut self) {
+        let mut shape = self.shapes.next_shape(self.chamber.height + 3);
         loop {
-            let (_, dir) = pattern.next();
-            shape.jet(dir, &chamber);
-            if !shape.fall(&chamber) {
+            shape.jet(self.pattern.next(), &self.chamber);
+            if !shape.fall(&self.chamber) {
                 break;
             }
         }
-        chamber.add_shape(shape);
-
-        if i % inc == 0 {
-            println!("done: {}", i);
-        }
+        self.chamber.add_shape(shape);
     }
 
-    chamber
+    // the next jet and rock, and the shape of the top of the tower
+    fn key(&self) -> (usize, usize, Vec<usize>) {
+        (self.pattern.next, self.shapes.next, self.chamber.drops())
+    }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let chamber = rounds(input, 2022, 100000);
-    println!("height: {}", chamber.height);
-    Ok(())
+fn rounds(jets: &[Dir], r: usize) -> Chamber {
+    let mut sim = Simulation::new(jets);
+    for _ in 0..r {
+        sim.drop_rock();
+    }
+    sim.chamber
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Values {
-    shapes: usize,
-    height: usize,
+fn tower_height(jets: &[Dir], rounds: usize) -> usize {
+    cycle::extrapolate(
+        Simulation::new(jets),
+        rounds,
+        Simulation::drop_rock,
+        Simulation::key,
+        |sim| sim.chamber.height as i64,
+    ) as usize
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
-
-    let mut pattern_idx;
-    let mut pattern = Pattern::new(input);
-    let mut shape_generator = ShapeGenerator::new();
-    let mut chamber = Chamber::new();
-
-    let mut seen: HashMap<(usize, Vec<usize>), Values> = HashMap::new();
+pub struct Day17;
 
-    let first: Values;
-    let second: Values;
-
-    let mut shapes = 0;
-    loop {
-        let mut shape = shape_generator.next_shape(chamber.height + 3);
-        loop {
-            let (idx, dir) = pattern.next();
-            pattern_idx = idx;
-
-            shape.jet(dir, &chamber);
-            if !shape.fall(&chamber) {
-                break;
-            }
-        }
-        chamber.add_shape(shape);
-        shapes += 1;
+impl Solution for Day17 {
+    type Input = Vec<Dir>;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-        let values = Values {
-            shapes,
-            height: chamber.height,
-        };
-        let state = (pattern_idx, chamber.drops());
-        if let Some(prev) = seen.get(&state) {
-            first = prev.clone();
-            second = values;
-            break;
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(ParseError::missing(input, "expected jets")
+                .at_line(1)
+                .into());
         }
-        seen.insert(state, values);
+        let jets = input
+            .char_indices()
+            .map(|(i, c)| match c {
+                '>' => Ok(Dir::Right),
+                '<' => Ok(Dir::Left),
+                _ => {
+                    let err =
+                        ParseError::new(input, &input[i..i + c.len_utf8()], "expected `<` or `>`");
+                    Err(err.at_line(1))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(jets)
     }
 
-    let repeat_shapes = second.shapes - first.shapes;
-    let repeat_height = second.height - first.height;
-
-    let rounds: usize = 1000000000000;
-    let remain = rounds - first.shapes;
-
-    let repeats = remain / repeat_shapes;
-    let last = remain % repeat_shapes;
-
-    // finish the last pieces
-    for _ in 0..last {
-        let mut shape = shape_generator.next_shape(chamber.height + 3);
-        loop {
-            let (_, dir) = pattern.next();
-            shape.jet(dir, &chamber);
-            if !shape.fall(&chamber) {
-                break;
-            }
-        }
-        chamber.add_shape(shape);
+    fn part1(input: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(rounds(input, 2022).height)
     }
 
-    let last_height = chamber.height - second.height;
+    fn part2(input: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(tower_height(input, 1000000000000))
+    }
+}
 
-    let final_height = first.height + repeats * repeat_height + last_height;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("height: {}", final_height);
+    #[test]
+    fn test_example() {
+        let jets = Day17::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day17::part1(&jets).unwrap(), 3068);
+        assert_eq!(Day17::part2(&jets).unwrap(), 1514285714288);
+    }
 
-    Ok(())
+    #[test]
+    fn test_parse_error() {
+        let err = Day17::parse("\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 1: expected jets, found end of line"
+        );
+        let err = Day17::parse("<<>x>").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 4: expected `<` or `>`, found \"x\""
+        );
+    }
 }
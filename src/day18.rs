@@ -1,9 +1,14 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     error::Error,
+    str::FromStr,
 };
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "2,2,2
 1,2,2
@@ -20,7 +25,7 @@ static TEST_INPUT: &str = "2,2,2
 2,3,5";
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Point {
+pub struct Point {
     x: i64,
     y: i64,
     z: i64,
@@ -52,14 +57,21 @@ impl Point {
     }
 }
 
-impl From<&str> for Point {
-    fn from(value: &str) -> Self {
-        let blah: Vec<_> = value.split(",").collect();
-        Self {
-            x: blah[0].parse().unwrap(),
-            y: blah[1].parse().unwrap(),
-            z: blah[2].parse().unwrap(),
+// e.g. 2,2,2
+impl FromStr for Point {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut coords = s.split(',');
+        let mut coord = || match coords.next() {
+            Some(c) => parse::number(s, c),
+            None => Err(ParseError::missing(s, "expected 3 coordinates")),
+        };
+        let point = Self::new(coord()?, coord()?, coord()?);
+        if let Some(extra) = coords.next() {
+            return Err(ParseError::new(s, extra, "expected 3 coordinates"));
         }
+        Ok(point)
     }
 }
 
@@ -107,30 +119,15 @@ fn sides(points: impl Iterator<Item = Point>) -> usize {
     side_count.iter().filter(|x| *x.1 == 1).count()
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let sides = sides(util::read_lines(input).map(Point::from));
-    println!("sides: {}", sides);
-    Ok(())
-}
-
 fn split_into_regions(mut hs: HashSet<Point>) -> Vec<HashSet<Point>> {
     let mut regions = Vec::new();
 
-    loop {
-        let p = match hs.iter().next() {
-            Some(p) => *p,
-            None => break,
-        };
-
+    while let Some(&p) = hs.iter().next() {
         let mut region = HashSet::new();
         let mut test = VecDeque::new();
         test.push_back(p);
 
-        loop {
-            let t = match test.pop_front() {
-                Some(t) => t,
-                None => break,
-            };
+        while let Some(t) = test.pop_front() {
             region.insert(t);
             hs.remove(&t);
 
@@ -148,9 +145,8 @@ fn split_into_regions(mut hs: HashSet<Point>) -> Vec<HashSet<Point>> {
     regions
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
-    let blocks: HashSet<Point> = util::read_lines(input).map(Point::from).collect();
+fn exterior_sides(blocks: &[Point]) -> usize {
+    let blocks: HashSet<Point> = blocks.iter().copied().collect();
 
     // get bounding area
     let mut min = *blocks.iter().next().unwrap();
@@ -184,8 +180,6 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
     max.y += 1;
     max.z += 1;
 
-    println!("bounding: {:?} -> {:?}", min, max);
-
     // get all spaces in the area
     let mut all = HashSet::new();
     for x in min.x..=max.x {
@@ -196,7 +190,7 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let space: HashSet<Point> = all.difference(&blocks).map(|p| *p).collect();
+    let space: HashSet<Point> = all.difference(&blocks).copied().collect();
 
     let regions = split_into_regions(space);
 
@@ -208,7 +202,37 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    println!("sides: {}", side_count);
+    side_count
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = Vec<Point>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::parse_lines(input)?)
+    }
+
+    fn part1(points: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(sides(points.iter().copied()))
+    }
+
+    fn part2(points: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(exterior_sides(points))
+    }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let points = Day18::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day18::part1(&points).unwrap(), 64);
+        assert_eq!(Day18::part2(&points).unwrap(), 58);
+    }
 }
@@ -1,23 +1,50 @@
 use std::{
-    collections::HashMap,
     error::Error,
-    fmt::Debug,
+    fmt::{Debug, Display},
     ops::{Index, IndexMut},
+    str::FromStr,
 };
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
 Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
 
-#[derive(Debug, Clone, Copy)]
-enum Resource {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
     Ore = 0,
     Clay = 1,
     Obsidian = 2,
     Geode = 3,
 }
 
+// geodes first, finding good plans early lets the bound cut more
+static KINDS: [Resource; 4] = [
+    Resource::Geode,
+    Resource::Obsidian,
+    Resource::Clay,
+    Resource::Ore,
+];
+
+// what the robots are paid with
+static SPENT: [Resource; 3] = [Resource::Ore, Resource::Clay, Resource::Obsidian];
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Resource::Ore => "ore",
+            Resource::Clay => "clay",
+            Resource::Obsidian => "obsidian",
+            Resource::Geode => "geode",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct Resources {
     geode: usize,
@@ -61,24 +88,30 @@ impl Resources {
     }
 }
 
-struct Blueprint {
+pub struct Blueprint {
     id: usize,
     robots: [Resources; 4],
 }
 
-impl From<&str> for Blueprint {
-    fn from(s: &str) -> Self {
-        let parts: Vec<_> = s.split_ascii_whitespace().collect();
+impl FromStr for Blueprint {
+    type Err = ParseError;
 
-        Self {
-            id: parts[1].strip_suffix(':').unwrap().parse().unwrap(),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cost = |n| parse::token(s, n, "a cost").and_then(|t| parse::number(s, t));
+        let id = parse::token(s, 1, "a blueprint id")?;
+        let id = id
+            .strip_suffix(':')
+            .ok_or_else(|| ParseError::new(s, id, "expected `:` after the id"))?;
+
+        Ok(Self {
+            id: parse::number(s, id)?,
             robots: [
-                Resources::new(parts[6].parse().unwrap(), 0, 0, 0),
-                Resources::new(parts[12].parse().unwrap(), 0, 0, 0),
-                Resources::new(parts[18].parse().unwrap(), parts[21].parse().unwrap(), 0, 0),
-                Resources::new(parts[27].parse().unwrap(), 0, parts[30].parse().unwrap(), 0),
+                Resources::new(cost(6)?, 0, 0, 0),
+                Resources::new(cost(12)?, 0, 0, 0),
+                Resources::new(cost(18)?, cost(21)?, 0, 0),
+                Resources::new(cost(27)?, 0, cost(30)?, 0),
             ],
-        }
+        })
     }
 }
 
@@ -90,13 +123,11 @@ struct Production {
 
 impl Production {
     fn can_build(&self, robot: &Resources) -> bool {
-        [Resource::Ore, Resource::Clay, Resource::Obsidian]
-            .into_iter()
-            .all(|r| self.resources[r] >= robot[r])
+        SPENT.into_iter().all(|r| self.resources[r] >= robot[r])
     }
 
     fn build(&mut self, robot: &Resources, kind: Resource) {
-        for r in [Resource::Ore, Resource::Clay, Resource::Obsidian] {
+        for r in SPENT {
             self.resources[r] -= robot[r];
         }
 
@@ -115,97 +146,159 @@ impl Production {
     }
 }
 
-impl Blueprint {
-    // Blueprint 1:
-    // Each ore robot costs 4 ore.
-    // Each clay robot costs 2 ore.
-    // Each obsidian robot costs 3 ore and 14 clay.
-    // Each geode robot costs 2 ore and 7 obsidian.
-    fn best(&self, rounds: usize) -> usize {
-        let mut prod = vec![Production {
-            robots: Resources::new(1, 0, 0, 0),
-            resources: Resources::new(0, 0, 0, 0),
-        }];
-
-        for _ in 0..rounds {
-            // println!("{}: {}", i, prod.len());
-            // for p in prod.iter() {
-            //     println!("  {:?}", p);
-            // }
-            let mut next = Vec::new();
-            for p in prod {
-                let mut p = p;
-
-                // first, check which robots we can create
-                let can_build: Vec<_> = [
-                    Resource::Ore,
-                    Resource::Clay,
-                    Resource::Obsidian,
-                    Resource::Geode,
-                ]
-                .into_iter()
-                .filter(|r| p.can_build(&self.robots[*r as usize]))
-                .collect();
-
-                // produce
-                p.produce();
-
-                // create robots
-                next.push(p.clone());
-                for r in can_build {
-                    let mut n = p.clone();
-                    n.build(&self.robots[r as usize], r);
-                    next.push(n);
-                }
+// a robot built during the 1 based minute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Build {
+    pub minute: usize,
+    pub robot: Resource,
+}
+
+impl Display for Build {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "minute {}: {} robot", self.minute, self.robot)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    pub geodes: usize,
+    pub builds: Vec<Build>,
+}
+
+struct Search<'a> {
+    blueprint: &'a Blueprint,
+    rounds: usize,
+    // there's no point having more robots of a kind than can be spent in
+    // a minute, as only one robot is built per minute
+    max_spend: Resources,
+    builds: Vec<Build>,
+    best: Plan,
+}
+
+impl Search<'_> {
+    // branches on which robot to build next, skipping the minutes spent
+    // waiting for it
+    fn dfs(&mut self, p: Production, left: usize) {
+        let geodes = p.resources.geode + p.robots.geode * left;
+        if geodes > self.best.geodes {
+            self.best = Plan {
+                geodes,
+                builds: self.builds.clone(),
+            };
+        }
+        if self.blueprint.upper_bound(&p, left) <= self.best.geodes {
+            return;
+        }
+
+        for kind in KINDS {
+            if kind != Resource::Geode && p.robots[kind] >= self.max_spend[kind] {
+                continue;
+            }
+            // a robot finished in the last minute can't make anything
+            let Some(wait) = self.blueprint.wait(&p, kind).filter(|w| w + 1 < left) else {
+                continue;
+            };
+
+            let mut next = p;
+            for _ in 0..=wait {
+                next.produce();
             }
+            next.build(&self.blueprint.robots[kind as usize], kind);
+
+            self.builds.push(Build {
+                minute: self.rounds - left + wait + 1,
+                robot: kind,
+            });
+            self.dfs(next, left - wait - 1);
+            self.builds.pop();
+        }
+    }
+}
 
-            // prune
-            let mut hm: HashMap<Resources, Vec<Resources>> = HashMap::new();
-            for n in next {
-                hm.entry(n.robots)
-                    .and_modify(|x| x.push(n.resources))
-                    .or_insert(vec![n.resources]);
+impl Blueprint {
+    // the minutes until there's enough to build the robot, None if the
+    // robots that mine what it costs haven't been built yet
+    fn wait(&self, p: &Production, kind: Resource) -> Option<usize> {
+        let cost = &self.robots[kind as usize];
+        SPENT.into_iter().try_fold(0, |wait, r| {
+            let short = cost[r].saturating_sub(p.resources[r]);
+            match (short, p.robots[r]) {
+                (0, _) => Some(wait),
+                (_, 0) => None,
+                (short, robots) => Some(wait.max(short.div_ceil(robots))),
             }
+        })
+    }
 
-            let mut next = Vec::new();
-            for (robots, mut resources) in hm {
-                resources.sort();
+    // the geodes possible if ore were free, a clay robot came every minute
+    // and clay and obsidian were never used up. the real production never
+    // has more robots of any kind, so it never beats this
+    fn upper_bound(&self, p: &Production, left: usize) -> usize {
+        let obsidian = self.robots[Resource::Obsidian as usize].clay;
+        let geode = self.robots[Resource::Geode as usize].obsidian;
+
+        let mut p = *p;
+        for _ in 0..left {
+            let obsidian_robot = p.resources.clay >= obsidian;
+            let geode_robot = p.resources.obsidian >= geode;
+            p.produce();
+            p.robots.clay += 1;
+            p.robots.obsidian += obsidian_robot as usize;
+            p.robots.geode += geode_robot as usize;
+        }
+        p.resources.geode
+    }
 
-                for r in resources.iter().rev().take(4) {
-                    next.push(Production {
-                        robots: robots.clone(),
-                        resources: r.clone(),
-                    });
-                }
+    // the most geodes that can be opened in the time, and the robots to
+    // build to get them
+    fn plan(&self, rounds: usize) -> Plan {
+        let mut max_spend = Resources::new(0, 0, 0, 0);
+        for robot in self.robots.iter() {
+            for r in SPENT {
+                max_spend[r] = max_spend[r].max(robot[r]);
             }
-
-            prod = next;
         }
 
-        prod.iter().map(|x| x.resources.geode).max().unwrap_or(0)
+        let mut search = Search {
+            blueprint: self,
+            rounds,
+            max_spend,
+            builds: Vec::new(),
+            best: Plan::default(),
+        };
+        let start = Production {
+            robots: Resources::new(1, 0, 0, 0),
+            resources: Resources::new(0, 0, 0, 0),
+        };
+        search.dfs(start, rounds);
+        search.best
+    }
+
+    fn best(&self, rounds: usize) -> usize {
+        self.plan(rounds).geodes
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let quality: usize = util::read_lines(input)
-        .map(Blueprint::from)
-        .map(|b| b.id * b.best(24))
-        .sum();
+pub struct Day19;
 
-    println!("quality: {}", quality);
+impl Solution for Day19 {
+    type Input = Vec<Blueprint>;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    Ok(())
-}
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::parse_lines(input)?)
+    }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let quality: usize = util::read_lines(input)
-        .take(3)
-        .map(Blueprint::from)
-        .map(|b| b.best(32))
-        .product();
+    fn part1(blueprints: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let best = util::par_map(blueprints, |b| b.id * b.best(24));
+        Ok(best.into_iter().sum())
+    }
 
-    println!("quality: {}", quality);
-    Ok(())
+    fn part2(blueprints: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let first = &blueprints[..blueprints.len().min(3)];
+        Ok(util::par_map(first, |b| b.best(32)).into_iter().product())
+    }
 }
 
 #[cfg(test)]
@@ -215,7 +308,7 @@ mod tests {
     #[test]
     fn test() {
         let s = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.";
-        let blueprint = Blueprint::from(s);
+        let blueprint: Blueprint = s.parse().unwrap();
         assert_eq!(1, blueprint.id);
         assert_eq!(4, blueprint.robots[Resource::Ore as usize].ore);
         assert_eq!(2, blueprint.robots[Resource::Clay as usize].ore);
@@ -224,4 +317,42 @@ mod tests {
         assert_eq!(2, blueprint.robots[Resource::Geode as usize].ore);
         assert_eq!(7, blueprint.robots[Resource::Geode as usize].obsidian);
     }
+
+    // follows the plan minute by minute, checking every build is affordable
+    fn replay(blueprint: &Blueprint, plan: &Plan, rounds: usize) -> usize {
+        let mut p = Production {
+            robots: Resources::new(1, 0, 0, 0),
+            resources: Resources::new(0, 0, 0, 0),
+        };
+        let mut builds = plan.builds.iter().peekable();
+        for minute in 1..=rounds {
+            let build = builds.next_if(|b| b.minute == minute);
+            if let Some(b) = build {
+                assert!(p.can_build(&blueprint.robots[b.robot as usize]), "{}", b);
+            }
+            p.produce();
+            if let Some(b) = build {
+                p.build(&blueprint.robots[b.robot as usize], b.robot);
+            }
+        }
+        assert_eq!(builds.next(), None);
+        p.resources.geode
+    }
+
+    #[test]
+    fn test_plan() {
+        let blueprints = Day19::parse(TEST_INPUT).unwrap();
+        for (b, rounds, geodes) in [(0, 24, 9), (1, 24, 12), (0, 32, 56), (1, 32, 62)] {
+            let plan = blueprints[b].plan(rounds);
+            assert_eq!(plan.geodes, geodes);
+            assert_eq!(replay(&blueprints[b], &plan, rounds), geodes);
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let blueprints = Day19::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day19::part1(&blueprints).unwrap(), 33);
+        assert_eq!(Day19::part2(&blueprints).unwrap(), 3472);
+    }
 }
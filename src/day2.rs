@@ -1,12 +1,18 @@
-use crate::util;
+use std::{error::Error, str::FromStr};
 
-enum RPS {
+use crate::{parse::ParseError, solution::Solution, util};
+
+static TEST_INPUT: &str = "A Y
+B X
+C Z";
+
+enum Rps {
     Rock,
     Paper,
     Scissors,
 }
 
-impl RPS {
+impl Rps {
     fn from_letter(l: &str) -> Self {
         match l {
             "A" => Self::Rock,
@@ -60,40 +66,69 @@ impl RPS {
     }
 }
 
-pub fn part1(input: &str) {
-    let lines = util::read_lines(input);
-
-    let score: i32 = lines
-        .filter(|line| *line != "")
-        .map(|line| {
-            let chars: Vec<&str> = line.split(' ').collect();
-            if chars.len() != 2 {
-                panic!("expected 2 chars, got >{}<", line)
-            }
-            (RPS::from_letter(chars[0]), RPS::from_letter(chars[1]))
+// the two letters of a round, e.g. `A Y`
+pub struct Round {
+    them: Rps,
+    // either our play or the result depending on the part
+    code: String,
+}
+
+impl FromStr for Round {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (them, code) = s
+            .split_once(' ')
+            .ok_or_else(|| ParseError::missing(s, "expected 2 letters"))?;
+        if !matches!(them, "A" | "B" | "C") {
+            return Err(ParseError::new(s, them, "expected A, B or C"));
+        }
+        if !matches!(code, "X" | "Y" | "Z") {
+            return Err(ParseError::new(s, code, "expected X, Y or Z"));
+        }
+        Ok(Self {
+            them: Rps::from_letter(them),
+            code: code.to_owned(),
         })
-        .map(|(them, me)| me.played_points() + RPS::result_points(&them, &me))
-        .sum();
+    }
+}
 
-    println!("score: {}", score);
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<Round>;
+    type Answer1 = i32;
+    type Answer2 = i32;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::parse_lines(input)?)
+    }
+
+    fn part1(rounds: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(rounds
+            .iter()
+            .map(|r| (&r.them, Rps::from_letter(&r.code)))
+            .map(|(them, me)| me.played_points() + Rps::result_points(them, &me))
+            .sum())
+    }
+
+    fn part2(rounds: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(rounds
+            .iter()
+            .map(|r| (&r.them, Rps::from_result(&r.code, &r.them)))
+            .map(|(them, me)| me.played_points() + Rps::result_points(them, &me))
+            .sum())
+    }
 }
 
-pub fn part2(input: &str) {
-    let lines = util::read_lines(input);
-
-    let score: i32 = lines
-        .filter(|line| *line != "")
-        .map(|line| {
-            let chars: Vec<&str> = line.split(' ').collect();
-            if chars.len() != 2 {
-                panic!("expected 2 chars, got >{}<", line)
-            }
-            let them = RPS::from_letter(chars[0]);
-            let me = RPS::from_result(chars[1], &them);
-            (them, me)
-        })
-        .map(|(them, me)| me.played_points() + RPS::result_points(&them, &me))
-        .sum();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("score: {}", score);
+    #[test]
+    fn test_example() {
+        let rounds = Day2::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day2::part1(&rounds).unwrap(), 15);
+        assert_eq!(Day2::part2(&rounds).unwrap(), 12);
+    }
 }
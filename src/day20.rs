@@ -1,6 +1,10 @@
 use std::error::Error;
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "1
 2
@@ -10,136 +14,262 @@ static TEST_INPUT: &str = "1
 0
 4";
 
-fn parse(s: &str) -> Vec<i64> {
-    util::read_lines(s).map(|x| x.parse().unwrap()).collect()
+fn parse(s: &str) -> Result<Vec<i64>, ParseError> {
+    util::numbered_lines(s)
+        .map(|(n, x)| parse::number(x, x).map_err(|e| e.at_line(n)))
+        .collect()
 }
 
-fn to_move(value: i64, len: usize) -> usize {
-    // because we remove the thing being moved
-    let len = len - 1;
-    let mut m = value;
-    if m < 0 {
-        let lens = (-m) / (len as i64) + 1;
-        m += lens * len as i64;
-    }
-    m as usize % len
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    left: usize,
+    right: usize,
+    parent: usize,
+    priority: u64,
+    // the nodes in this subtree, which gives the position in the sequence
+    size: usize,
 }
 
-struct Mix {
-    values: Vec<i64>,
-    zero: usize,
-    moves: Vec<usize>,
-    prev: Vec<usize>,
-    next: Vec<usize>,
+// an implicit treap, the ids 0..n in some order kept in a binary tree by
+// their position. random priorities keep it balanced, so finding, removing
+// and inserting are O(log n)
+struct Sequence {
+    nodes: Vec<Node>,
+    root: usize,
 }
 
-impl Mix {
-    fn new(values: Vec<i64>) -> Self {
-        let zero = values.iter().position(|x| *x == 0).unwrap();
-        let moves: Vec<usize> = values.iter().map(|x| to_move(*x, values.len())).collect();
-        let prev: Vec<usize> = (0..values.len())
-            .map(|x| (values.len() + x - 1) % values.len())
+impl Sequence {
+    fn new(len: usize) -> Self {
+        // xorshift, any spread of priorities will do
+        let mut seed = 0x2545f4914f6cdd1d_u64;
+        let nodes = (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                Node {
+                    left: NIL,
+                    right: NIL,
+                    parent: NIL,
+                    priority: seed,
+                    size: 1,
+                }
+            })
             .collect();
-        let next: Vec<usize> = (0..values.len()).map(|x| (x + 1) % values.len()).collect();
-        Self {
-            values,
-            zero,
-            moves,
-            prev,
-            next,
-        }
-    }
 
-    fn mov(&mut self, i: usize) {
-        if self.moves[i] == 0 {
-            return;
+        let mut seq = Self { nodes, root: NIL };
+        for id in 0..len {
+            seq.root = seq.merge(seq.root, id);
         }
+        seq.detach(seq.root);
+        seq
+    }
 
-        // remove the thing being moved
-        let n = self.next[i];
-        let p = self.prev[i];
-        self.next[p] = n;
-        self.prev[n] = p;
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
 
-        // find where we go
-        let mut after = i;
-        for _ in 0..self.moves[i] {
-            after = self.next[after];
+    fn size(&self, t: usize) -> usize {
+        if t == NIL {
+            0
+        } else {
+            self.nodes[t].size
         }
+    }
 
-        let n = self.next[after];
-        self.next[after] = i;
-        self.prev[i] = after;
-        self.next[i] = n;
-        self.prev[n] = i;
+    fn detach(&mut self, t: usize) {
+        if t != NIL {
+            self.nodes[t].parent = NIL;
+        }
     }
 
-    fn print_from_zero(&self) {
-        let mut i = self.zero;
-        loop {
-            print!("{}, ", self.values[i]);
-            i = self.next[i];
-            if i == self.zero {
-                break;
+    // fixes up t after its children have changed
+    fn update(&mut self, t: usize) {
+        let Node { left, right, .. } = self.nodes[t];
+        self.nodes[t].size = 1 + self.size(left) + self.size(right);
+        for child in [left, right] {
+            if child != NIL {
+                self.nodes[child].parent = t;
             }
         }
-        println!();
     }
 
-    fn find_from_zero(&self, n: usize) -> i64 {
-        let mut i = self.zero;
-        let n = n % self.values.len();
-        for _ in 0..n {
-            i = self.next[i];
+    // everything in a followed by everything in b
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            self.nodes[a].right = self.merge(self.nodes[a].right, b);
+            self.update(a);
+            a
+        } else {
+            self.nodes[b].left = self.merge(a, self.nodes[b].left);
+            self.update(b);
+            b
         }
-        self.values[i]
     }
-}
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
+    // the first k of t, and the rest
+    fn split(&mut self, t: usize, k: usize) -> (usize, usize) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        let left = self.nodes[t].left;
+        if self.size(left) >= k {
+            let (a, b) = self.split(left, k);
+            self.nodes[t].left = b;
+            self.update(t);
+            (a, t)
+        } else {
+            let k = k - self.size(left) - 1;
+            let (a, b) = self.split(self.nodes[t].right, k);
+            self.nodes[t].right = a;
+            self.update(t);
+            (t, b)
+        }
+    }
 
-    let values = parse(input);
-    let mut mix = Mix::new(values);
+    // where the id is, counting up the tree everything before it
+    fn index_of(&self, id: usize) -> usize {
+        let mut index = self.size(self.nodes[id].left);
+        let mut t = id;
+        while self.nodes[t].parent != NIL {
+            let parent = self.nodes[t].parent;
+            if self.nodes[parent].right == t {
+                index += self.size(self.nodes[parent].left) + 1;
+            }
+            t = parent;
+        }
+        index
+    }
 
-    for i in 0..mix.values.len() {
-        mix.mov(i);
+    fn get(&self, mut index: usize) -> usize {
+        let mut t = self.root;
+        loop {
+            let left = self.size(self.nodes[t].left);
+            match index.cmp(&left) {
+                std::cmp::Ordering::Less => t = self.nodes[t].left,
+                std::cmp::Ordering::Equal => return t,
+                std::cmp::Ordering::Greater => {
+                    index -= left + 1;
+                    t = self.nodes[t].right;
+                }
+            }
+        }
     }
 
-    let v1000 = mix.find_from_zero(1000);
-    let v2000 = mix.find_from_zero(2000);
-    let v3000 = mix.find_from_zero(3000);
+    fn remove_at(&mut self, index: usize) -> usize {
+        let (a, rest) = self.split(self.root, index);
+        let (id, b) = self.split(rest, 1);
+        self.root = self.merge(a, b);
+        self.detach(self.root);
+        self.detach(id);
+        id
+    }
 
-    println!("{}, {}, {}", v1000, v2000, v3000);
+    fn insert_at(&mut self, index: usize, id: usize) {
+        let (a, b) = self.split(self.root, index);
+        self.detach(a);
+        self.detach(b);
+        let a = self.merge(a, id);
+        self.root = self.merge(a, b);
+        self.detach(self.root);
+    }
+}
 
-    let sum = v1000 + v2000 + v3000;
+// the values after mixing, starting from the one that was first
+fn mix(values: &[i64], rounds: usize) -> Vec<i64> {
+    let len = values.len();
+    let mut seq = Sequence::new(len);
+    // moving round the other len - 1 values gets back to the same place
+    if len > 1 {
+        for _ in 0..rounds {
+            for (id, &value) in values.iter().enumerate() {
+                let from = seq.index_of(id);
+                seq.remove_at(from);
+                let to = (from as i64 + value).rem_euclid(len as i64 - 1);
+                seq.insert_at(to as usize, id);
+            }
+        }
+    }
+    (0..len).map(|i| values[seq.get(i)]).collect()
+}
 
-    println!("sum: {}", sum);
+// the sum of the 1000th, 2000th and 3000th values after the 0
+fn decrypt(values: &[i64], key: i64, rounds: usize) -> Result<i64, String> {
+    let values = values
+        .iter()
+        .map(|x| x.checked_mul(key))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("the key {} is too big", key))?;
 
-    Ok(())
+    let mixed = mix(&values, rounds);
+    let zero = mixed.iter().position(|&x| x == 0).ok_or("there's no 0")?;
+    Ok([1000, 2000, 3000]
+        .iter()
+        .map(|n| mixed[(zero + n) % mixed.len()])
+        .sum())
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
-    let values = parse(input).iter().map(|x| *x * 811589153).collect();
-    let mut mix = Mix::new(values);
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = Vec<i64>;
+    type Answer1 = i64;
+    type Answer2 = i64;
 
-    // mix 10 times
-    for j in 0..10 {
-        println!("round: {}", j);
-        for i in 0..mix.values.len() {
-            mix.mov(i);
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        let values = parse(input)?;
+        if !values.contains(&0) {
+            return Err(ParseError::missing(input, "expected a 0").into());
         }
+        Ok(values)
     }
 
-    let v1000 = mix.find_from_zero(1000);
-    let v2000 = mix.find_from_zero(2000);
-    let v3000 = mix.find_from_zero(3000);
+    fn part1(values: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(decrypt(values, 1, 1)?)
+    }
 
-    println!("{}, {}, {}", v1000, v2000, v3000);
+    fn part2(values: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(decrypt(values, 811589153, 10)?)
+    }
+}
 
-    let sum = v1000 + v2000 + v3000;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let values = Day20::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day20::part1(&values).unwrap(), 3);
+        assert_eq!(Day20::part2(&values).unwrap(), 1623178306);
+    }
 
-    println!("sum: {}", sum);
-    Ok(())
+    #[test]
+    fn test_mix() {
+        let values = Day20::parse(TEST_INPUT).unwrap();
+        // the example's 1, 2, -3, 4, 0, 3, -2 going round the circle
+        assert_eq!(mix(&values, 1), [-2, 1, 2, -3, 4, 0, 3]);
+
+        // against moving the values around a plain Vec
+        let values: Vec<i64> = (0..200).map(|i| (i * 7919 % 601) - 300).collect();
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        for _ in 0..3 {
+            for (id, &value) in values.iter().enumerate() {
+                let from = order.iter().position(|&x| x == id).unwrap();
+                order.remove(from);
+                let to = (from as i64 + value).rem_euclid(values.len() as i64 - 1);
+                order.insert(to as usize, id);
+            }
+        }
+        let expected: Vec<_> = order.iter().map(|&id| values[id]).collect();
+        assert_eq!(mix(&values, 3), expected);
+    }
 }
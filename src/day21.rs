@@ -1,6 +1,14 @@
-use std::{collections::HashMap, error::Error, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+};
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "root: pppw + sjmn
 dbpl: 5
@@ -18,8 +26,8 @@ lgvd: ljgn * ptdq
 drzm: hmdt - zczc
 hmdt: 32";
 
-#[derive(Debug, Clone, Copy)]
-enum Op {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
     Plus,
     Minus,
     Mul,
@@ -41,147 +49,403 @@ impl Display for Op {
     }
 }
 
-impl From<&str> for Op {
-    fn from(value: &str) -> Self {
+impl TryFrom<&str> for Op {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            "+" => Self::Plus,
-            "-" => Self::Minus,
-            "*" => Self::Mul,
-            "/" => Self::Div,
-            _ => panic!("bad op"),
+            "+" => Ok(Self::Plus),
+            "-" => Ok(Self::Minus),
+            "*" => Ok(Self::Mul),
+            "/" => Ok(Self::Div),
+            _ => Err(ParseError::new(value, value, "expected +, -, * or /")),
         }
     }
 }
 
-enum MonkeyOp {
+#[derive(Debug, Clone)]
+pub enum MonkeyOp {
     Num(i64),
     Op(String, Op, String),
 }
 
-fn parse(value: &str) -> (String, MonkeyOp) {
+// e.g. root: pppw + sjmn
+fn parse(value: &str) -> Result<(String, MonkeyOp), ParseError> {
     let parts: Vec<_> = value.split_ascii_whitespace().collect();
-    let id = parts[0].strip_suffix(':').unwrap().to_owned();
-    match parts.len() {
-        2 => (id, MonkeyOp::Num(parts[1].parse().unwrap())),
-        4 => (
-            id,
-            MonkeyOp::Op(parts[1].to_owned(), Op::from(parts[2]), parts[3].to_owned()),
-        ),
-        x => panic!("bad monkey {}", x),
-    }
-}
-
-fn derive(id: &str, ops: &HashMap<String, MonkeyOp>, derived: &mut HashMap<String, i64>) {
-    if let Some(op) = ops.get(id) {
-        match op {
-            MonkeyOp::Num(x) => {
-                derived.insert(id.to_owned(), *x);
-            }
+    let id = parse::token(value, 0, "a monkey name")?;
+    let id = id
+        .strip_suffix(':')
+        .ok_or_else(|| ParseError::new(value, id, "expected `:` after the name"))?
+        .to_owned();
+    match parts[..] {
+        [_, x] => Ok((id, MonkeyOp::Num(parse::number(value, x)?))),
+        [_, a, op, b] => {
+            let op = Op::try_from(op).map_err(|e| e.within(value, op))?;
+            Ok((id, MonkeyOp::Op(a.to_owned(), op, b.to_owned())))
+        }
+        [_] => Err(ParseError::missing(
+            value,
+            "expected a number or an operation",
+        )),
+        [_, _, _] => Err(ParseError::missing(value, "expected a second monkey")),
+        _ => Err(ParseError::new(value, parts[4], "expected end of line")),
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// an exact fraction, kept in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    fn new(num: i128, den: i128) -> Self {
+        let g = gcd(num, den) * den.signum();
+        Self {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    fn int(n: i64) -> Self {
+        Self::new(n as i128, 1)
+    }
+
+    fn to_int(self) -> Result<i64, String> {
+        if self.den != 1 {
+            return Err(format!("{} isn't a whole number", self));
+        }
+        i64::try_from(self.num).map_err(|_| format!("{} is too big", self))
+    }
+
+    // None on overflow or dividing by zero
+    fn apply(self, op: Op, other: Self) -> Option<Self> {
+        let (a, b, c, d) = (self.num, self.den, other.num, other.den);
+        let (num, den) = match op {
+            Op::Plus => (
+                a.checked_mul(d)?.checked_add(c.checked_mul(b)?)?,
+                b.checked_mul(d)?,
+            ),
+            Op::Minus => (
+                a.checked_mul(d)?.checked_sub(c.checked_mul(b)?)?,
+                b.checked_mul(d)?,
+            ),
+            Op::Mul => (a.checked_mul(c)?, b.checked_mul(d)?),
+            Op::Div if c == 0 => return None,
+            Op::Div => (a.checked_mul(d)?, b.checked_mul(c)?),
+        };
+        Some(Self::new(num, den))
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn apply(a: Rational, op: Op, b: Rational) -> Result<Rational, String> {
+    a.apply(op, b)
+        .ok_or_else(|| format!("can't work out {} {} {}", a, op, b))
+}
+
+// what a monkey yells, with everything that doesn't depend on the human
+// worked out already
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(Rational),
+    Humn,
+    Op(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    // `humn` is left unknown when `unknown` is set
+    fn build(
+        id: &str,
+        ops: &HashMap<String, MonkeyOp>,
+        unknown: bool,
+        visiting: &mut HashSet<String>,
+    ) -> Result<Expr, String> {
+        if unknown && id == "humn" {
+            return Ok(Expr::Humn);
+        }
+        let op = ops
+            .get(id)
+            .ok_or_else(|| format!("there's no monkey {}", id))?;
+        if !visiting.insert(id.to_owned()) {
+            return Err(format!("monkey {} depends on itself", id));
+        }
+
+        let expr = match op {
+            MonkeyOp::Num(x) => Expr::Num(Rational::int(*x)),
             MonkeyOp::Op(a, op, b) => {
-                derive(a, ops, derived);
-                derive(b, ops, derived);
-                match (derived.get(a), derived.get(b)) {
-                    (Some(a), Some(b)) => {
-                        let x = match op {
-                            Op::Plus => a + b,
-                            Op::Minus => a - b,
-                            Op::Div => a / b,
-                            Op::Mul => a * b,
-                        };
-                        derived.insert(id.to_owned(), x);
-                    }
-                    _ => {}
+                let a = Self::build(a, ops, unknown, visiting)?;
+                let b = Self::build(b, ops, unknown, visiting)?;
+                match (a, b) {
+                    (Expr::Num(a), Expr::Num(b)) => Expr::Num(apply(a, *op, b)?),
+                    (a, b) => Expr::Op(Box::new(a), *op, Box::new(b)),
                 }
             }
+        };
+        visiting.remove(id);
+        Ok(expr)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Num(x) => write!(f, "{}", x),
+            Expr::Humn => write!(f, "humn"),
+            Expr::Op(a, op, b) => write!(f, "({} {} {})", a, op, b),
         }
     }
 }
 
-fn dump(ops: &HashMap<String, MonkeyOp>) {
-    let mut keys: Vec<_> = ops.keys().collect();
-    keys.sort();
-    for id in keys.iter() {
-        match &ops[*id] {
-            MonkeyOp::Num(x) => println!("{}: {}", id, x),
-            MonkeyOp::Op(a, op, b) => println!("{}: {} {} {}", id, a, op, b),
+// a·humn + b
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Linear {
+    a: Rational,
+    b: Rational,
+}
+
+impl Expr {
+    fn linear(&self) -> Result<Linear, String> {
+        let zero = Rational::int(0);
+        match self {
+            Expr::Num(x) => Ok(Linear { a: zero, b: *x }),
+            Expr::Humn => Ok(Linear {
+                a: Rational::int(1),
+                b: zero,
+            }),
+            Expr::Op(l, op, r) => {
+                let (l, r) = (l.linear()?, r.linear()?);
+                match op {
+                    Op::Plus | Op::Minus => Ok(Linear {
+                        a: apply(l.a, *op, r.a)?,
+                        b: apply(l.b, *op, r.b)?,
+                    }),
+                    Op::Mul if l.a == zero => Ok(Linear {
+                        a: apply(l.b, Op::Mul, r.a)?,
+                        b: apply(l.b, Op::Mul, r.b)?,
+                    }),
+                    Op::Mul if r.a == zero => Ok(Linear {
+                        a: apply(l.a, Op::Mul, r.b)?,
+                        b: apply(l.b, Op::Mul, r.b)?,
+                    }),
+                    Op::Mul => Err(format!("{} isn't linear in humn", self)),
+                    Op::Div if r.a == zero => Ok(Linear {
+                        a: apply(l.a, Op::Div, r.b)?,
+                        b: apply(l.b, Op::Div, r.b)?,
+                    }),
+                    Op::Div => Err(format!("{} isn't linear in humn, it divides by humn", self)),
+                }
+            }
         }
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
+// root's two monkeys have to yell the same number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation {
+    lhs: Expr,
+    rhs: Expr,
+}
 
-    let ops: HashMap<String, MonkeyOp> = util::read_lines(input).map(parse).collect();
-    let mut derived = HashMap::new();
-    // dump(&ops);
+impl Equation {
+    fn new(ops: &HashMap<String, MonkeyOp>) -> Result<Self, String> {
+        let Some(MonkeyOp::Op(a, _, b)) = ops.get("root") else {
+            return Err("root should be an operation".to_owned());
+        };
+        let mut visiting = HashSet::from(["root".to_owned()]);
+        Ok(Self {
+            lhs: Expr::build(a, ops, true, &mut visiting)?,
+            rhs: Expr::build(b, ops, true, &mut visiting)?,
+        })
+    }
 
-    derive("root", &ops, &mut derived);
+    // both sides as a·humn + b, so humn = (b2 - b1) / (a1 - a2)
+    fn solve(&self) -> Result<Rational, String> {
+        let (l, r) = match (self.lhs.linear(), self.rhs.linear()) {
+            (Ok(l), Ok(r)) => (l, r),
+            // dividing by humn isn't linear, but can still be undone
+            // while humn is only on one side
+            (Err(e), _) | (_, Err(e)) => return self.unwind().map_err(|_| e),
+        };
+        let a = apply(l.a, Op::Minus, r.a)?;
+        let b = apply(r.b, Op::Minus, l.b)?;
+        if a == Rational::int(0) {
+            return Err(match b == Rational::int(0) {
+                true => format!("any humn solves {}", self),
+                false => format!("no humn solves {}", self),
+            });
+        }
+        apply(b, Op::Div, a)
+    }
 
-    println!("result: {}", derived["root"]);
+    // undoes the operations around humn one at a time, which only works
+    // while every operation has humn on just one side
+    fn unwind(&self) -> Result<Rational, String> {
+        let (mut expr, mut value) = match (&self.lhs, &self.rhs) {
+            (e, Expr::Num(v)) | (Expr::Num(v), e) => (e, *v),
+            _ => return Err(format!("humn is on both sides of {}", self)),
+        };
 
-    Ok(())
+        loop {
+            let Expr::Op(a, op, b) = expr else {
+                return match expr {
+                    Expr::Humn => Ok(value),
+                    _ => Err(format!("humn isn't in {}", self)),
+                };
+            };
+            value = match (a.as_ref(), b.as_ref()) {
+                (Expr::Num(a), e) => {
+                    expr = e;
+                    match op {
+                        Op::Plus => apply(value, Op::Minus, *a)?,
+                        Op::Minus => apply(*a, Op::Minus, value)?,
+                        Op::Mul => apply(value, Op::Div, *a)?,
+                        Op::Div => apply(*a, Op::Div, value)?,
+                    }
+                }
+                (e, Expr::Num(b)) => {
+                    expr = e;
+                    match op {
+                        Op::Plus => apply(value, Op::Minus, *b)?,
+                        Op::Minus => apply(value, Op::Plus, *b)?,
+                        Op::Mul => apply(value, Op::Div, *b)?,
+                        Op::Div => apply(value, Op::Mul, *b)?,
+                    }
+                }
+                _ => return Err(format!("humn is on both sides of {}", expr)),
+            };
+        }
+    }
 }
 
-fn derive_backwards(
-    id: &str,
-    value: i64,
-    ops: &HashMap<String, MonkeyOp>,
-    derived: &HashMap<String, i64>,
-) -> i64 {
-    if id == "humn" {
-        return value;
+impl Display for Equation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
     }
-    match &ops[id] {
-        MonkeyOp::Num(_) => panic!("unexpected num arm for, {}", id),
-        MonkeyOp::Op(a, op, b) => {
-            // expect one of the arms to be derived
-            if let Some(&a) = derived.get(a) {
-                let v = match op {
-                    Op::Plus => value - a,
-                    Op::Minus => a - value,
-                    Op::Mul => value / a,
-                    Op::Div => a / value,
-                };
+}
 
-                derive_backwards(b, v, ops, derived)
-            } else if let Some(&b) = derived.get(b) {
-                let v = match op {
-                    Op::Plus => value - b,
-                    Op::Minus => value + b,
-                    Op::Mul => value / b,
-                    Op::Div => value * b,
-                };
+pub struct Day21;
 
-                derive_backwards(a, v, ops, derived)
-            } else {
-                panic!("didn't find derived values for {} or {}", a, b);
-            }
+impl Solution for Day21 {
+    type Input = HashMap<String, MonkeyOp>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::numbered_lines(input)
+            .map(|(n, line)| parse(line).map_err(|e| e.at_line(n)))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(ops: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        match Expr::build("root", ops, false, &mut HashSet::new())? {
+            Expr::Num(x) => Ok(x.to_int()?),
+            expr => Err(format!("couldn't work out {}", expr).into()),
         }
     }
+
+    fn part2(ops: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(Equation::new(ops)?.solve()?.to_int()?)
+    }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    // let input = TEST_INPUT;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let ops: HashMap<String, MonkeyOp> = util::read_lines(input)
-        .map(parse)
-        .filter(|(id, _)| id != "humn")
-        .collect();
-    let mut derived = HashMap::new();
+    #[test]
+    fn test_example() {
+        let ops = Day21::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day21::part1(&ops).unwrap(), 152);
+        assert_eq!(Day21::part2(&ops).unwrap(), 301);
+    }
 
-    derive("root", &ops, &mut derived);
+    #[test]
+    fn test_parse_error() {
+        let err = Day21::parse("root: a +").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 10: expected a second monkey, found end of line"
+        );
+        let err = Day21::parse("root: a + b c").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 13: expected end of line, found \"c\""
+        );
+    }
 
-    if let MonkeyOp::Op(a, _, b) = &ops["root"] {
-        let result = if let Some(&a) = derived.get(a) {
-            derive_backwards(b, a, &ops, &derived)
-        } else if let Some(&b) = derived.get(b) {
-            derive_backwards(a, b, &ops, &derived)
-        } else {
-            panic!("didn't find derived values for {} or {}", a, b);
-        };
-        // FIXME for some reason this is always zero??
-        println!("result: {}", result);
+    fn solve(s: &str) -> Result<Rational, String> {
+        let ops = Day21::parse(s).unwrap();
+        Equation::new(&ops)?.solve()
     }
 
-    Ok(())
+    #[test]
+    fn test_equation() {
+        let ops = Day21::parse(TEST_INPUT).unwrap();
+        let equation = Equation::new(&ops).unwrap();
+        assert_eq!(equation.to_string(), "((4 + (2 * (humn - 3))) / 4) = 150");
+
+        // exact division, even where integers would round
+        assert_eq!(
+            solve("root: a + b\na: c * d\nb: 3\nc: humn / e\nd: 2\ne: 4\nhumn: 1"),
+            Ok(Rational::int(6))
+        );
+        assert_eq!(
+            solve("root: a + b\na: c / humn\nb: 2\nc: 3\nhumn: 1"),
+            Ok(Rational::new(3, 2))
+        );
+
+        // humn on both sides, but still linear
+        assert_eq!(
+            solve("root: a + b\na: humn * c\nb: humn - c\nc: 2\nhumn: 1"),
+            Ok(Rational::int(-2))
+        );
+        assert_eq!(
+            solve("root: a + b\na: humn + humn\nb: 4\nhumn: 1"),
+            Ok(Rational::int(2))
+        );
+        assert_eq!(
+            solve("root: a + b\na: c / d\nb: 3\nc: humn + humn\nd: 3\nhumn: 1"),
+            Ok(Rational::new(9, 2))
+        );
+
+        assert_eq!(
+            solve("root: a + b\na: humn * humn\nb: 2\nhumn: 1"),
+            Err("(humn * humn) isn't linear in humn".to_owned())
+        );
+        assert_eq!(
+            solve("root: a + b\na: c / humn\nb: humn * c\nc: 3\nhumn: 1"),
+            Err("(3 / humn) isn't linear in humn, it divides by humn".to_owned())
+        );
+        assert_eq!(
+            solve("root: a + b\na: humn + c\nb: humn - c\nc: 2\nhumn: 1"),
+            Err("no humn solves (humn + 2) = (humn - 2)".to_owned())
+        );
+        assert_eq!(
+            solve("root: a + b\na: humn * c\nb: humn + humn\nc: 2\nhumn: 1"),
+            Err("any humn solves (humn * 2) = (humn + humn)".to_owned())
+        );
+        assert_eq!(
+            solve("root: a + b\na: b * c\nb: a + c\nc: 2\nhumn: 1"),
+            Err("monkey a depends on itself".to_owned())
+        );
+    }
 }
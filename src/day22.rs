@@ -1,5 +1,11 @@
 use std::{collections::HashMap, error::Error, fmt::Display};
 
+use crate::{
+    grid::{Point, SparseGrid},
+    parse::ParseError,
+    solution::Solution,
+};
+
 static TEST_INPUT: &str = r#"        ...#
         .#..
         #...
@@ -56,97 +62,98 @@ impl Dir {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-struct Loc(i64, i64);
+impl Dir {
+    fn step(&self) -> Point {
+        match self {
+            Self::Left => Point::LEFT,
+            Self::Right => Point::RIGHT,
+            Self::Up => Point::UP,
+            Self::Down => Point::DOWN,
+        }
+    }
+}
 
-impl Loc {
-    fn next(&self, dir: Dir) -> Self {
-        match dir {
-            Dir::Left => Loc(self.0 - 1, self.1),
-            Dir::Right => Loc(self.0 + 1, self.1),
-            Dir::Up => Loc(self.0, self.1 - 1),
-            Dir::Down => Loc(self.0, self.1 + 1),
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tile::Floor => write!(f, "."),
+            Tile::Wall => write!(f, "#"),
         }
     }
 }
 
 #[derive(Debug)]
-struct Map {
-    tiles: HashMap<Loc, Tile>,
+pub struct Map {
+    tiles: SparseGrid<Tile>,
     ops: Vec<Op>,
-    start: Loc,
-    tops: HashMap<i64, Loc>,
-    bottoms: HashMap<i64, Loc>,
-    rights: HashMap<i64, Loc>,
-    lefts: HashMap<i64, Loc>,
+    start: Point,
+    tops: HashMap<i64, Point>,
+    bottoms: HashMap<i64, Point>,
+    rights: HashMap<i64, Point>,
+    lefts: HashMap<i64, Point>,
 }
 
 impl Map {
-    fn parse(s: &str) -> Self {
-        let mut tiles = HashMap::new();
-        let mut iter = s.trim_end().split('\n');
-        let mut start = None;
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        let (map, path) = s
+            .trim_end()
+            .split_once("\n\n")
+            .ok_or_else(|| ParseError::missing("", "expected the path after the map"))?;
+        let tiles = SparseGrid::parse(map, &[' '], "` `, `.` or `#`", |c| match c {
+            '.' => Some(Tile::Floor),
+            '#' => Some(Tile::Wall),
+            _ => None,
+        })?;
+        let ops = Self::parse_ops(path).map_err(|e| e.at_line(map.lines().count() + 2))?;
+
+        // work out limits, row by row
+        let mut points: Vec<_> = tiles.points().collect();
+        points.sort_by_key(|p| (p.y, p.x));
 
         let mut tops = HashMap::new();
         let mut bottoms = HashMap::new();
         let mut rights = HashMap::new();
         let mut lefts = HashMap::new();
-
-        for (y, line) in (&mut iter).enumerate() {
-            let y = y as i64;
-            if line == "" {
-                break;
-            }
-            for (x, c) in line.chars().enumerate() {
-                let x = x as i64;
-                let loc = Loc(x, y);
-
-                match c {
-                    ' ' => {}
-                    '.' => {
-                        tiles.insert(loc, Tile::Floor);
-                        if start.is_none() {
-                            start = Some(loc);
-                        }
-                    }
-                    '#' => {
-                        tiles.insert(loc, Tile::Wall);
-                    }
-                    _ => panic!("what is this? {}", c),
-                }
-
-                // work out limits
-                if matches!(c, '.' | '#') {
-                    lefts.entry(y).or_insert(loc);
-                    tops.entry(x).or_insert(loc);
-                    rights.insert(y, loc);
-                    bottoms.insert(x, loc);
-                }
-            }
+        for &p in points.iter() {
+            lefts.entry(p.y).or_insert(p);
+            tops.entry(p.x).or_insert(p);
+            rights.insert(p.y, p);
+            bottoms.insert(p.x, p);
         }
 
-        let ops = Self::parse_ops(iter.next().unwrap());
+        let start = points
+            .into_iter()
+            .find(|&p| tiles.get(p) == Some(&Tile::Floor))
+            .ok_or_else(|| ParseError::missing("", "expected an open tile"))?;
 
-        Self {
+        Ok(Self {
             tiles,
             ops,
-            start: start.unwrap(),
+            start,
             tops,
             bottoms,
             rights,
             lefts,
-        }
+        })
     }
 
-    fn parse_ops(s: &str) -> Vec<Op> {
+    fn parse_ops(s: &str) -> Result<Vec<Op>, ParseError> {
         let mut ops = Vec::new();
         let mut num: usize = 0;
+        // where the current number started
+        let mut start = 0;
 
-        for c in s.chars() {
+        for (i, c) in s.char_indices() {
             match c {
                 '0'..='9' => {
+                    if !s[..i].ends_with(|c: char| c.is_ascii_digit()) {
+                        start = i;
+                    }
                     let x = c as usize - '0' as usize;
-                    num = num * 10 + x;
+                    num = num
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(x))
+                        .ok_or_else(|| ParseError::new(s, &s[start..=i], "number is too big"))?;
                 }
                 'R' => {
                     if num > 0 {
@@ -162,7 +169,10 @@ impl Map {
                     }
                     ops.push(Op::Left);
                 }
-                _ => panic!("what is this? {}", c),
+                _ => {
+                    let bad = &s[i..i + c.len_utf8()];
+                    return Err(ParseError::new(s, bad, "expected a number, `L` or `R`"));
+                }
             }
         }
 
@@ -171,18 +181,17 @@ impl Map {
             ops.push(Op::Move(num));
         }
 
-        ops
+        Ok(ops)
     }
 }
 
 impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let maxx = self.tiles.keys().map(|l| l.0).max().unwrap();
-        let maxy = self.tiles.keys().map(|l| l.1).max().unwrap();
+        let max = self.tiles.bounds().ok_or(std::fmt::Error)?.max;
 
-        for y in 0..=maxy {
-            for x in 0..=maxx {
-                match self.tiles.get(&Loc(x, y)) {
+        for y in 0..=max.y {
+            for x in 0..=max.x {
+                match self.tiles.get(Point::new(x, y)) {
                     None => {
                         write!(f, " ")?;
                     }
@@ -195,10 +204,10 @@ impl Display for Map {
                 }
             }
 
-            write!(f, " {:?} -> {:?}\n", self.lefts[&y], self.rights[&y])?;
+            writeln!(f, " {:?} -> {:?}", self.lefts[&y], self.rights[&y])?;
         }
 
-        write!(f, "\n")?;
+        writeln!(f)?;
 
         for op in self.ops.iter() {
             match op {
@@ -221,7 +230,7 @@ impl Display for Map {
 struct MapWalker<'a> {
     map: &'a Map,
     dir: Dir,
-    current: Loc,
+    current: Point,
 }
 
 impl<'a> MapWalker<'a> {
@@ -232,18 +241,18 @@ impl<'a> MapWalker<'a> {
             dir: Dir::Right,
         }
     }
-    fn wrap(&self) -> Loc {
+    fn wrap(&self) -> Point {
         match self.dir {
-            Dir::Left => self.map.rights[&self.current.1],
-            Dir::Right => self.map.lefts[&self.current.1],
-            Dir::Up => self.map.bottoms[&self.current.0],
-            Dir::Down => self.map.tops[&self.current.0],
+            Dir::Left => self.map.rights[&self.current.y],
+            Dir::Right => self.map.lefts[&self.current.y],
+            Dir::Up => self.map.bottoms[&self.current.x],
+            Dir::Down => self.map.tops[&self.current.x],
         }
     }
 
     fn mov(&mut self) {
-        let next = self.current.next(self.dir);
-        let next = match self.map.tiles.get(&next) {
+        let next = self.current + self.dir.step();
+        let next = match self.map.tiles.get(next) {
             Some(Tile::Floor) => next,
             Some(Tile::Wall) => self.current,
             None => self.wrap(),
@@ -284,20 +293,10 @@ impl<'a> MapWalker<'a> {
     }
 
     fn password(&self) -> i64 {
-        1000 * (self.current.1 + 1) + 4 * (self.current.0 + 1) + self.dir.facing()
+        1000 * (self.current.y + 1) + 4 * (self.current.x + 1) + self.dir.facing()
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let map = Map::parse(input);
-    let mut mw = MapWalker::new(&map);
-    mw.run();
-
-    println!("password: {:?}", mw.password());
-
-    Ok(())
-}
-
 struct Edge {
     x: i64,
     y: i64,
@@ -305,10 +304,10 @@ struct Edge {
 }
 
 impl Edge {
-    fn new(l: Loc, dir: Dir) -> Self {
+    fn new(l: Point, dir: Dir) -> Self {
         Self {
-            x: l.0,
-            y: l.1,
+            x: l.x,
+            y: l.y,
             dir,
         }
     }
@@ -318,7 +317,7 @@ impl Edge {
         e2: Edge,
         edge_size: i64,
         rev: bool,
-        wraps: &mut HashMap<(Loc, Dir), (Loc, Dir)>,
+        wraps: &mut HashMap<(Point, Dir), (Point, Dir)>,
     ) {
         let l1 = e1.locs(edge_size);
         let mut l2 = e2.locs(edge_size);
@@ -327,115 +326,208 @@ impl Edge {
             l2 = l2.into_iter().rev().collect();
         }
 
-        for (l1, l2) in l1.into_iter().zip(l2.into_iter()) {
+        for (l1, l2) in l1.into_iter().zip(l2) {
             wraps.insert((l1, e1.dir), (l2, e2.dir.opp()));
             wraps.insert((l2, e2.dir), (l1, e1.dir.opp()));
         }
     }
 
-    fn locs(&self, edge_size: i64) -> Vec<Loc> {
+    fn locs(&self, edge_size: i64) -> Vec<Point> {
         match self.dir {
             Dir::Up => {
                 let y = self.y * edge_size;
                 (self.x * edge_size..(self.x + 1) * edge_size)
-                    .map(|x| Loc(x, y))
+                    .map(|x| Point::new(x, y))
                     .collect()
             }
             Dir::Down => {
                 let y = (self.y + 1) * edge_size - 1;
                 (self.x * edge_size..(self.x + 1) * edge_size)
-                    .map(|x| Loc(x, y))
+                    .map(|x| Point::new(x, y))
                     .collect()
             }
             Dir::Left => {
                 let x = self.x * edge_size;
                 (self.y * edge_size..(self.y + 1) * edge_size)
-                    .map(|y| Loc(x, y))
+                    .map(|y| Point::new(x, y))
                     .collect()
             }
             Dir::Right => {
                 let x = (self.x + 1) * edge_size - 1;
                 (self.y * edge_size..(self.y + 1) * edge_size)
-                    .map(|y| Loc(x, y))
+                    .map(|y| Point::new(x, y))
                     .collect()
             }
         }
     }
 }
 
-struct CubeWalker<'a> {
-    map: &'a Map,
-    dir: Dir,
-    current: Loc,
-    wraps: HashMap<(Loc, Dir), (Loc, Dir)>,
+// a 3d direction, each face's axes are ±1 along one of x, y or z
+type Vector = [i64; 3];
+
+fn neg(v: Vector) -> Vector {
+    v.map(|c| -c)
 }
 
-impl<'a> CubeWalker<'a> {
-    fn new(map: &'a Map) -> Self {
-        let size = map.tiles.keys().map(|l| l.0).max().unwrap();
+// a face of the net, `right` and `down` are the 3d directions its net x
+// and y point in once it's folded up, `normal` points out of the cube
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    net: Point,
+    normal: Vector,
+    right: Vector,
+    down: Vector,
+}
 
-        let real = size > 20;
-        let edge_size = if real { (size + 1) / 3 } else { (size + 1) / 4 };
-        let mut wraps = HashMap::new();
+impl Face {
+    // where an edge leads
+    fn towards(&self, dir: Dir) -> Vector {
+        match dir {
+            Dir::Right => self.right,
+            Dir::Left => neg(self.right),
+            Dir::Down => self.down,
+            Dir::Up => neg(self.down),
+        }
+    }
+
+    // the 3d direction the edge's points are listed in by `Edge::locs`
+    fn along(&self, dir: Dir) -> Vector {
+        match dir {
+            Dir::Up | Dir::Down => self.right,
+            Dir::Left | Dir::Right => self.down,
+        }
+    }
 
-        println!("size: {}, edge_size: {}", size, edge_size);
+    // the face next to this one in the net, folded down over the edge
+    fn roll(&self, dir: Dir) -> Face {
+        let mut face = *self;
+        face.net = self.net + dir.step();
+        face.normal = self.towards(dir);
+        match dir {
+            Dir::Right => face.right = neg(self.normal),
+            Dir::Left => face.right = self.normal,
+            Dir::Down => face.down = neg(self.normal),
+            Dir::Up => face.down = self.normal,
+        }
+        face
+    }
+}
 
-        let edges = if !real {
-            // the numbered sides
-            let s1 = Loc(2, 0);
-            let s2 = Loc(0, 1);
-            let s3 = Loc(1, 1);
-            let s4 = Loc(2, 1);
-            let s5 = Loc(2, 2);
-            let s6 = Loc(3, 2);
+static DIRS: [Dir; 4] = [Dir::Right, Dir::Down, Dir::Left, Dir::Up];
 
-            vec![
-                (Edge::new(s1, Dir::Left), Edge::new(s3, Dir::Up), false),
-                (Edge::new(s1, Dir::Up), Edge::new(s2, Dir::Up), true),
-                (Edge::new(s1, Dir::Right), Edge::new(s6, Dir::Right), true),
-                (Edge::new(s2, Dir::Left), Edge::new(s6, Dir::Down), true),
-                (Edge::new(s2, Dir::Down), Edge::new(s5, Dir::Down), true),
-                (Edge::new(s3, Dir::Down), Edge::new(s5, Dir::Left), true),
-                (Edge::new(s6, Dir::Up), Edge::new(s4, Dir::Right), true),
-            ]
-        } else {
-            // _12
-            // _3_
-            // 45_
-            // 6__
-
-            // the numbered sides
-            let s1 = Loc(1, 0);
-            let s2 = Loc(2, 0);
-            let s3 = Loc(1, 1);
-            let s4 = Loc(0, 2);
-            let s5 = Loc(1, 2);
-            let s6 = Loc(0, 3);
+// two edges glued together, and whether one runs the opposite way to the
+// other
+type Seam = (Edge, Edge, bool);
 
-            vec![
-                (Edge::new(s1, Dir::Up), Edge::new(s6, Dir::Left), false),
-                (Edge::new(s1, Dir::Left), Edge::new(s4, Dir::Left), true),
-                (Edge::new(s2, Dir::Up), Edge::new(s6, Dir::Down), false),
-                (Edge::new(s2, Dir::Right), Edge::new(s5, Dir::Right), true),
-                (Edge::new(s2, Dir::Down), Edge::new(s3, Dir::Right), false),
-                (Edge::new(s3, Dir::Left), Edge::new(s4, Dir::Up), false),
-                (Edge::new(s5, Dir::Down), Edge::new(s6, Dir::Right), false),
-            ]
-        };
+// folds the map into a cube, giving the face size and the seams
+fn fold(tiles: &SparseGrid<Tile>) -> Result<(i64, Vec<Seam>), String> {
+    let area = tiles.len() as i64;
+    let size = (1..).find(|s| 6 * s * s >= area).unwrap_or(0);
+    if 6 * size * size != area {
+        return Err(format!("{} tiles can't make 6 square faces", area));
+    }
 
+    let mut counts: HashMap<Point, i64> = HashMap::new();
+    for p in tiles.points() {
+        *counts
+            .entry(Point::new(p.x / size, p.y / size))
+            .or_default() += 1;
+    }
+    if let Some((p, _)) = counts.iter().find(|&(_, &n)| n != size * size) {
+        return Err(format!(
+            "the face at column {}, row {} isn't a full {}x{} square",
+            p.x * size + 1,
+            p.y * size + 1,
+            size,
+            size
+        ));
+    }
+
+    // unfold from the top left face, sitting it on top of the cube
+    let first = counts.keys().min_by_key(|p| (p.y, p.x)).copied();
+    let mut faces: Vec<Face> = first
+        .map(|net| Face {
+            net,
+            normal: [0, 0, 1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        })
+        .into_iter()
+        .collect();
+    let mut i = 0;
+    while let Some(&face) = faces.get(i) {
+        for dir in DIRS {
+            let next = face.roll(dir);
+            if counts.contains_key(&next.net) && faces.iter().all(|f| f.net != next.net) {
+                faces.push(next);
+            }
+        }
+        i += 1;
+    }
+
+    let mut normals: Vec<_> = faces.iter().map(|f| f.normal).collect();
+    normals.sort();
+    normals.dedup();
+    if faces.len() != counts.len() || normals.len() != 6 {
+        return Err("the map doesn't fold into a cube".to_owned());
+    }
+
+    // every edge that isn't already joined in the net is glued to the
+    // edge of the face it folds onto
+    let mut edges = Vec::new();
+    for (i, f) in faces.iter().enumerate() {
+        for dir in DIRS {
+            if counts.contains_key(&(f.net + dir.step())) {
+                continue;
+            }
+            let (j, g) = faces
+                .iter()
+                .enumerate()
+                .find(|(_, g)| g.normal == f.towards(dir))
+                .unwrap();
+            let other = DIRS
+                .into_iter()
+                .find(|&d| g.towards(d) == f.normal)
+                .unwrap();
+            // each pair once
+            if (i, dir.facing()) < (j, other.facing()) {
+                edges.push((
+                    Edge::new(f.net, dir),
+                    Edge::new(g.net, other),
+                    f.along(dir) != g.along(other),
+                ));
+            }
+        }
+    }
+
+    Ok((size, edges))
+}
+
+struct CubeWalker<'a> {
+    map: &'a Map,
+    dir: Dir,
+    current: Point,
+    wraps: HashMap<(Point, Dir), (Point, Dir)>,
+}
+
+impl<'a> CubeWalker<'a> {
+    fn new(map: &'a Map) -> Result<Self, String> {
+        let (edge_size, edges) = fold(&map.tiles)?;
+
+        let mut wraps = HashMap::new();
         for (e1, e2, rev) in edges {
             Edge::map(e1, e2, edge_size, rev, &mut wraps);
         }
 
-        Self {
+        Ok(Self {
             map,
             current: map.start,
             dir: Dir::Right,
             wraps,
-        }
+        })
     }
 
-    fn wrap(&mut self) -> (Loc, Dir) {
+    fn wrap(&mut self) -> (Point, Dir) {
         if let Some((loc, dir)) = self.wraps.get(&(self.current, self.dir)) {
             (*loc, *dir)
         } else {
@@ -444,11 +536,11 @@ impl<'a> CubeWalker<'a> {
     }
 
     fn mov(&mut self) {
-        let mut next = (self.current.next(self.dir), self.dir);
-        if self.map.tiles.get(&next.0).is_none() {
+        let mut next = (self.current + self.dir.step(), self.dir);
+        if !self.map.tiles.contains(next.0) {
             next = self.wrap();
         }
-        if *self.map.tiles.get(&next.0).unwrap() == Tile::Wall {
+        if self.map.tiles.get(next.0) == Some(&Tile::Wall) {
             // no move to make!
             return;
         };
@@ -489,19 +581,34 @@ impl<'a> CubeWalker<'a> {
     }
 
     fn password(&self) -> i64 {
-        1000 * (self.current.1 + 1) + 4 * (self.current.0 + 1) + self.dir.facing()
+        1000 * (self.current.y + 1) + 4 * (self.current.x + 1) + self.dir.facing()
     }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let map = Map::parse(input);
-    let mut cw = CubeWalker::new(&map);
+pub struct Day22;
 
-    cw.run();
+impl Solution for Day22 {
+    type Input = Map;
+    type Answer1 = i64;
+    type Answer2 = i64;
 
-    println!("password: {:?}", cw.password());
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Map::parse(input)?)
+    }
+
+    fn part1(map: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let mut mw = MapWalker::new(map);
+        mw.run();
+
+        Ok(mw.password())
+    }
 
-    Ok(())
+    fn part2(map: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let mut cw = CubeWalker::new(map)?;
+        cw.run();
+
+        Ok(cw.password())
+    }
 }
 
 #[cfg(test)]
@@ -510,7 +617,7 @@ mod tests {
 
     #[test]
     fn test_parse_ops() {
-        let v = Map::parse_ops("10R12L20");
+        let v = Map::parse_ops("10R12L20").unwrap();
         assert_eq!(
             v,
             vec![
@@ -521,5 +628,70 @@ mod tests {
                 Op::Move(20)
             ]
         );
+
+        let err = Map::parse_ops("10R123456789012345678901L2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "column 4: number is too big, found \"123456789012345678901\""
+        );
+    }
+
+    // a map of open tiles, with a face for every `#` in the layout
+    fn net(layout: &str, size: usize) -> Map {
+        let mut s = String::new();
+        for row in layout.lines() {
+            let line: String = row
+                .chars()
+                .map(|c| {
+                    if c == '#' {
+                        ".".repeat(size)
+                    } else {
+                        " ".repeat(size)
+                    }
+                })
+                .collect();
+            for _ in 0..size {
+                s += &line;
+                s += "\n";
+            }
+        }
+        s += "\n1";
+        Map::parse(&s).unwrap()
+    }
+
+    #[test]
+    fn test_fold() {
+        for layout in [
+            "..#.\n###.\n..##",
+            ".##\n.#.\n##.\n#..",
+            "#...\n####\n...#",
+            ".#..\n####\n.#..",
+            "##..\n.##.\n..##",
+            "###..\n..###",
+        ] {
+            for size in [1, 3] {
+                let map = net(layout, size);
+                let cube = CubeWalker::new(&map).unwrap();
+                assert_eq!(cube.wraps.len(), 14 * size, "{}", layout);
+                // going over an edge and turning round comes straight back
+                for (&(p, d), &(q, e)) in cube.wraps.iter() {
+                    assert_eq!(cube.wraps[&(q, e.opp())], (p, d.opp()), "{}", layout);
+                }
+            }
+        }
+
+        for layout in ["###\n###", "####\n##..", "#####\n#...."] {
+            let map = net(layout, 2);
+            assert!(CubeWalker::new(&map).is_err(), "{}", layout);
+        }
+        let map = Map::parse("..\n.\n\n1").unwrap();
+        assert!(CubeWalker::new(&map).is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let map = Day22::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day22::part1(&map).unwrap(), 6032);
+        assert_eq!(Day22::part2(&map).unwrap(), 5031);
     }
 }
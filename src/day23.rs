@@ -1,7 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
     error::Error,
-    ops::Add,
+};
+
+use crate::{
+    grid::{Bounds, Point, SparseGrid},
+    parse::ParseError,
+    solution::Solution,
 };
 
 static TEST_INPUT: &str = "..............
@@ -74,99 +79,68 @@ impl Dir {
             _ => panic!("bad direction"),
         }
     }
-    fn loc(&self) -> Loc {
+    fn step(&self) -> Point {
         match self {
-            Self::N => Loc(0, -1),
-            Self::S => Loc(0, 1),
-            Self::W => Loc(-1, 0),
-            Self::E => Loc(1, 0),
-            Self::NE => Loc(1, -1),
-            Self::NW => Loc(-1, -1),
-            Self::SE => Loc(1, 1),
-            Self::SW => Loc(-1, 1),
+            Self::N => Point::UP,
+            Self::S => Point::DOWN,
+            Self::W => Point::LEFT,
+            Self::E => Point::RIGHT,
+            Self::NE => Point::UP + Point::RIGHT,
+            Self::NW => Point::UP + Point::LEFT,
+            Self::SE => Point::DOWN + Point::RIGHT,
+            Self::SW => Point::DOWN + Point::LEFT,
         }
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-struct Loc(i64, i64);
+fn propose(elf: Point, current: &HashSet<Point>, dir: Dir) -> Option<Point> {
+    let occupied: HashSet<Dir> = Dir::ALL
+        .into_iter()
+        .filter(|c| current.contains(&(elf + c.step())))
+        .collect();
 
-impl Add for Loc {
-    type Output = Loc;
+    // println!("loc: {:?}, occupied: {:?}", self, occupied);
 
-    fn add(self, rhs: Loc) -> Self::Output {
-        Loc(self.0 + rhs.0, self.1 + rhs.1)
+    if occupied.is_empty() {
+        return None;
     }
-}
 
-impl Loc {
-    fn propose(&self, current: &HashSet<Loc>, dir: Dir) -> Option<Loc> {
-        let occupied: HashSet<Dir> = Dir::ALL
-            .into_iter()
-            .filter(|c| current.contains(&self.add(c.loc())))
-            .collect();
-
-        // println!("loc: {:?}, occupied: {:?}", self, occupied);
-
-        if occupied.is_empty() {
-            return None;
+    for d in dir.order() {
+        // println!("dir: {:?}, check: {:?}", d, d.check());
+        if !d.check().iter().any(|d| occupied.contains(d)) {
+            return Some(elf + d.step());
         }
-
-        for d in dir.order() {
-            // println!("dir: {:?}, check: {:?}", d, d.check());
-            if !d.check().iter().any(|d| occupied.contains(d)) {
-                return Some(self.add(d.loc()));
-            }
-        }
-
-        None
     }
-}
 
-fn parse(s: &str) -> HashSet<Loc> {
-    let mut hs = HashSet::new();
-    for (y, line) in s.trim().split('\n').enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            if c == '#' {
-                hs.insert(Loc(x as i64, y as i64));
-            }
-        }
-    }
-    hs
+    None
 }
 
-fn print(map: &HashSet<Loc>) {
-    let minx = map.iter().map(|x| x.0).min().unwrap();
-    let maxx = map.iter().map(|x| x.0).max().unwrap();
-    let miny = map.iter().map(|x| x.1).min().unwrap();
-    let maxy = map.iter().map(|x| x.1).max().unwrap();
+fn parse(s: &str) -> Result<HashSet<Point>, ParseError> {
+    let elves = SparseGrid::parse(s, &['.'], "`.` or `#`", |c| (c == '#').then_some('#'))?;
+    Ok(elves.points().collect())
+}
 
-    for y in (miny - 3)..(maxy + 3) {
-        for x in (minx - 3)..(maxx + 3) {
-            print!("{}", if map.contains(&Loc(x, y)) { "#" } else { "." });
-        }
-        println!("  {}", y);
+fn render(elves: &HashSet<Point>) -> String {
+    let mut grid = SparseGrid::new();
+    for &elf in elves {
+        grid.insert(elf, '#');
     }
+    grid.to_string()
 }
 
-fn rectangle(map: &HashSet<Loc>) -> i64 {
-    let minx = map.iter().map(|x| x.0).min().unwrap();
-    let maxx = map.iter().map(|x| x.0).max().unwrap();
-    let miny = map.iter().map(|x| x.1).min().unwrap();
-    let maxy = map.iter().map(|x| x.1).max().unwrap();
-
-    (1 + maxx - minx) * (1 + maxy - miny)
+fn rectangle(elves: &HashSet<Point>) -> i64 {
+    Bounds::of(elves.iter().copied()).map_or(0, |b| b.area())
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut occupied = parse(input);
+fn empty_ground(occupied: &HashSet<Point>, rounds: usize) -> i64 {
+    let mut occupied = occupied.clone();
     let mut dir = Dir::N;
 
-    for _ in 0..10 {
+    for _ in 0..rounds {
         // dest => [src]
-        let mut proposed: HashMap<Loc, Vec<Loc>> = HashMap::new();
+        let mut proposed: HashMap<Point, Vec<Point>> = HashMap::new();
         for loc in occupied.iter() {
-            if let Some(next) = loc.propose(&occupied, dir) {
+            if let Some(next) = propose(*loc, &occupied, dir) {
                 proposed
                     .entry(next)
                     .and_modify(|v| v.push(*loc))
@@ -185,21 +159,18 @@ pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
         dir = dir.next();
     }
 
-    let rectangle = rectangle(&occupied);
-    println!("{}", rectangle - occupied.len() as i64);
-
-    Ok(())
+    rectangle(&occupied) - occupied.len() as i64
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut occupied = parse(input);
+fn settle(occupied: &HashSet<Point>) -> usize {
+    let mut occupied = occupied.clone();
     let mut dir = Dir::N;
 
-    for i in 0..1000 {
+    for round in 1.. {
         // dest => [src]
-        let mut proposed: HashMap<Loc, Vec<Loc>> = HashMap::new();
+        let mut proposed: HashMap<Point, Vec<Point>> = HashMap::new();
         for loc in occupied.iter() {
-            if let Some(next) = loc.propose(&occupied, dir) {
+            if let Some(next) = propose(*loc, &occupied, dir) {
                 proposed
                     .entry(next)
                     .and_modify(|v| v.push(*loc))
@@ -219,8 +190,7 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
             .collect();
 
         if moves.is_empty() {
-            println!("{}", i + 1);
-            break;
+            return round;
         }
 
         // updated
@@ -232,5 +202,37 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
         dir = dir.next();
     }
 
-    Ok(())
+    unreachable!()
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    type Input = HashSet<Point>;
+    type Answer1 = i64;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(parse(input)?)
+    }
+
+    fn part1(occupied: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(empty_ground(occupied, 10))
+    }
+
+    fn part2(occupied: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(settle(occupied))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let occupied = Day23::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day23::part1(&occupied).unwrap(), 110);
+        assert_eq!(Day23::part2(&occupied).unwrap(), 20);
+    }
 }
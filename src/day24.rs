@@ -1,6 +1,11 @@
-use std::{collections::HashSet, error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, iter};
 
-use crate::util;
+use crate::{
+    grid::{Bounds, Point},
+    parse::ParseError,
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "#.######
 #>>.<^<#
@@ -28,252 +33,368 @@ impl Display for Dir {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-struct Loc {
-    x: i64,
-    y: i64,
+fn point(x: usize, y: usize) -> Point {
+    Point::new(x as i64, y as i64)
 }
 
-impl Loc {
-    fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// one bit per cell of a row or column
+#[derive(Debug, Clone)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
     }
-    fn nu(x: usize, y: usize) -> Self {
+}
+
+// the blizzards only ever move along their row or column, so where they
+// started is enough to say where they are at any minute
+#[derive(Debug, Clone)]
+struct Blizzards {
+    // by row
+    left: Vec<Bits>,
+    right: Vec<Bits>,
+    // by column
+    up: Vec<Bits>,
+    down: Vec<Bits>,
+}
+
+impl Blizzards {
+    fn new(width: usize, height: usize) -> Self {
         Self {
-            x: x as i64,
-            y: y as i64,
+            left: vec![Bits::new(width); height],
+            right: vec![Bits::new(width); height],
+            up: vec![Bits::new(height); width],
+            down: vec![Bits::new(height); width],
+        }
+    }
+
+    // x and y are from the top left inside the walls
+    fn add(&mut self, x: usize, y: usize, dir: Dir) {
+        match dir {
+            Dir::Left => self.left[y].set(x),
+            Dir::Right => self.right[y].set(x),
+            Dir::Up => self.up[x].set(y),
+            Dir::Down => self.down[x].set(y),
         }
     }
-}
 
-impl Default for Loc {
-    fn default() -> Self {
-        Self { x: 0, y: 0 }
+    // the blizzards at x, y after `t` minutes, each one is back where it
+    // started after going the width or height of the valley
+    fn at(&self, x: usize, y: usize, t: usize) -> impl Iterator<Item = Dir> + '_ {
+        let (w, h) = (self.up.len(), self.left.len());
+        [
+            (Dir::Left, self.left[y].get((x + t) % w)),
+            (Dir::Right, self.right[y].get((x + w - t % w) % w)),
+            (Dir::Up, self.up[x].get((y + t) % h)),
+            (Dir::Down, self.down[x].get((y + h - t % h) % h)),
+        ]
+        .into_iter()
+        .filter_map(|(dir, here)| here.then_some(dir))
     }
 }
 
-#[derive(Debug)]
-struct Blizzard {
-    loc: Loc,
-    dir: Dir,
+// how long the waypoints took, with where we were at every minute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub time: usize,
+    pub path: Vec<Point>,
 }
 
-#[derive(Debug)]
-struct Valley {
-    start: Loc,
-    end: Loc,
-    blizzards: Vec<Blizzard>,
+#[derive(Debug, Clone)]
+pub struct Valley {
+    start: Point,
+    end: Point,
+    blizzards: Blizzards,
     // the area inside the wall
-    // (top left, bottom right)
-    min: Loc,
-    max: Loc,
+    inside: Bounds,
+    // the blizzards are all back where they started after this long
+    period: usize,
 }
 
 impl Valley {
-    fn parse(s: &str) -> Self {
-        let lines: Vec<_> = util::read_lines(s).collect();
-
-        let min = Loc { x: 1, y: 1 };
-        let mut max = Loc::default();
-        let mut start = Loc::default();
-        let mut end = Loc::default();
-        let mut blizzards = Vec::new();
-        let last_line = lines.len() - 1;
-
-        for (y, line) in lines.into_iter().enumerate() {
-            let chars: Vec<_> = line.chars().collect();
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        let lines: Vec<_> = util::numbered_lines(s).collect();
+        let width = lines.first().map_or(0, |(_, l)| l.chars().count());
+        if lines.len() < 3 || width < 3 {
+            return Err(ParseError::missing(s, "expected a walled valley"));
+        }
+
+        let (w, h) = (width - 2, lines.len() - 2);
+        let inside = Bounds::new(Point::new(1, 1), point(w, h));
+        let mut start = Point::default();
+        let mut end = Point::default();
+        let mut blizzards = Blizzards::new(w, h);
+
+        // the gap in the top or bottom wall
+        let gap = |n: usize, line: &str| {
+            line.find('.')
+                .ok_or_else(|| ParseError::missing(line, "expected a gap in the wall").at_line(n))
+        };
+
+        for (y, (n, line)) in lines.into_iter().enumerate() {
             if y == 0 {
-                max = Loc::nu(chars.len() - 2, last_line - 1);
                 // find the start
-                let x = chars.iter().position(|x| *x == '.').unwrap();
-                start = Loc::nu(x, y);
-            } else if y == last_line {
+                start = point(gap(n, line)?, y);
+            } else if y == h + 1 {
                 // find the end
-                let x = chars.iter().position(|x| *x == '.').unwrap();
-                end = Loc::nu(x, y);
+                end = point(gap(n, line)?, y);
             } else {
                 // find blizzards
-                for (x, c) in chars.into_iter().enumerate() {
+                for (x, (i, c)) in line.char_indices().enumerate() {
                     let dir = match c {
                         '^' => Dir::Up,
                         'v' => Dir::Down,
                         '<' => Dir::Left,
                         '>' => Dir::Right,
-                        _ => continue,
+                        '.' | '#' => continue,
+                        _ => {
+                            let bad = &line[i..i + c.len_utf8()];
+                            let err = ParseError::new(line, bad, "expected a wall or blizzard");
+                            return Err(err.at_line(n));
+                        }
                     };
-                    let loc = Loc::nu(x, y);
-                    blizzards.push(Blizzard { loc, dir });
+                    if !(1..=w).contains(&x) {
+                        let bad = &line[i..i + c.len_utf8()];
+                        let err =
+                            ParseError::new(line, bad, "expected a blizzard inside the walls");
+                        return Err(err.at_line(n));
+                    }
+                    blizzards.add(x - 1, y - 1, dir);
                 }
             }
         }
 
-        Self {
+        Ok(Self {
             start,
             end,
             blizzards,
-            min,
-            max,
-        }
+            inside,
+            period: w / gcd(w, h) * h,
+        })
     }
 
-    fn step(&mut self) {
-        for b in self.blizzards.iter_mut() {
-            match b.dir {
-                Dir::Up => {
-                    if b.loc.y == self.min.y {
-                        b.loc.y = self.max.y;
-                    } else {
-                        b.loc.y -= 1;
-                    }
-                }
-                Dir::Down => {
-                    if b.loc.y == self.max.y {
-                        b.loc.y = self.min.y;
-                    } else {
-                        b.loc.y += 1;
-                    }
-                }
-                Dir::Left => {
-                    if b.loc.x == self.min.x {
-                        b.loc.x = self.max.x;
-                    } else {
-                        b.loc.x -= 1;
-                    }
-                }
-                Dir::Right => {
-                    if b.loc.x == self.max.x {
-                        b.loc.x = self.min.x;
-                    } else {
-                        b.loc.x += 1;
-                    }
-                }
-            }
-        }
+    fn blizzards_at(&self, p: Point, t: usize) -> impl Iterator<Item = Dir> + '_ {
+        let inside = self.inside.contains(p);
+        let (x, y) = if inside {
+            (
+                (p.x - self.inside.min.x) as usize,
+                (p.y - self.inside.min.y) as usize,
+            )
+        } else {
+            (0, 0)
+        };
+        self.blizzards.at(x, y, t).filter(move |_| inside)
     }
 
-    fn dump(&self) {
-        for y in self.min.y..=self.max.y {
-            for x in self.min.x..=self.max.x {
-                let bs: Vec<_> = self
-                    .blizzards
-                    .iter()
-                    .filter(|b| b.loc.x == x && b.loc.y == y)
-                    .collect();
-
-                match bs.len() {
-                    0 => print!("."),
-                    1 => print!("{}", bs[0].dir),
-                    x => print!("{}", x),
-                };
-            }
-            println!();
-        }
+    fn contains(&self, l: &Point) -> bool {
+        *l == self.start || *l == self.end || self.inside.contains(*l)
     }
 
-    fn contains(&self, l: &Loc) -> bool {
-        *l == self.start
-            || *l == self.end
-            || (l.x >= self.min.x && l.x <= self.max.x && l.y >= self.min.y && l.y <= self.max.y)
+    // whether we can be at p at minute t
+    fn is_clear(&self, p: Point, t: usize) -> bool {
+        self.contains(&p) && self.blizzards_at(p, t).next().is_none()
     }
-}
-
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut valley = Valley::parse(input);
-
-    // the set of possible positions
-    let mut pos = HashSet::new();
-    pos.insert(valley.start);
-
-    'label: for i in 0..1000 {
-        valley.step();
-
-        // all of the occupied spaces after the step
-        let occ: HashSet<Loc> = valley.blizzards.iter().map(|b| b.loc).collect();
 
-        let mut new = HashSet::new();
+    // the valley at minute t, drawn like the puzzle does
+    fn render(&self, t: usize) -> String {
+        let Bounds { min, max } = self.inside;
+        let mut s = String::new();
+        for y in min.y - 1..=max.y + 1 {
+            for x in min.x - 1..=max.x + 1 {
+                let p = Point::new(x, y);
+                let here: Vec<_> = self.blizzards_at(p, t).collect();
+                match here[..] {
+                    _ if !self.contains(&p) => s.push('#'),
+                    [] => s.push('.'),
+                    [dir] => s += &dir.to_string(),
+                    _ => s += &here.len().to_string(),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
 
-        for p in pos.iter() {
-            let next = [
-                Loc::new(p.x, p.y),
-                Loc::new(p.x, p.y + 1),
-                Loc::new(p.x, p.y - 1),
-                Loc::new(p.x + 1, p.y),
-                Loc::new(p.x - 1, p.y),
-            ];
+    // the quickest way from the first waypoint through each of the others
+    // in turn. states are a place, the minute within the blizzard period and
+    // how many waypoints have been reached, so a repeat is never worth
+    // exploring and the search ends once it runs out of new ones
+    pub fn plan(&self, waypoints: &[Point]) -> Option<Route> {
+        let (&first, rest) = waypoints.split_first()?;
+        if !self.is_clear(first, 0) {
+            return None;
+        }
 
-            // check if we can get to the end
-            if next.contains(&valley.end) {
-                println!("done: {}", i + 1);
-                break 'label;
+        // every place we can be, with the walls around the inside
+        let area = Bounds::new(
+            self.inside.min - Point::new(0, 1),
+            self.inside.max + Point::new(0, 1),
+        );
+        let cells = area.area() as usize;
+        let cell = |p: Point| ((p.y - area.min.y) * area.width() + p.x - area.min.x) as usize;
+        let state = |p: Point, t: usize, reached: usize| {
+            (reached * self.period + t % self.period) * cells + cell(p)
+        };
+        let place = |s: usize| {
+            let cell = (s % cells) as i64;
+            area.min + Point::new(cell % area.width(), cell / area.width())
+        };
+
+        // waypoints we're already at count straight away
+        let reached = rest.iter().take_while(|&&p| p == first).count();
+        // the state each one was first reached from, u32 keeps the table
+        // small as there are millions for the real valley
+        let mut from = vec![u32::MAX; cells * self.period * (rest.len() + 1)];
+        let start = state(first, 0, reached);
+        from[start] = start as u32;
+        let mut frontier = vec![(first, reached)];
+
+        for t in 0.. {
+            if frontier.is_empty() {
+                return None;
+            }
+            if let Some(&(p, _)) = frontier.iter().find(|&&(_, reached)| reached == rest.len()) {
+                // back through where we came from
+                let mut path = vec![p];
+                let mut s = state(p, t, rest.len());
+                while from[s] as usize != s {
+                    s = from[s] as usize;
+                    path.push(place(s));
+                }
+                path.reverse();
+                return Some(Route { time: t, path });
             }
 
-            for n in next {
-                if valley.contains(&n) && !occ.contains(&n) {
-                    new.insert(n);
+            let mut next = Vec::new();
+            for (p, reached) in frontier {
+                let here = state(p, t, reached);
+                // wait, or move
+                for n in iter::once(p).chain(p.neighbours4()) {
+                    if !self.is_clear(n, t + 1) {
+                        continue;
+                    }
+                    // and any after it in the same place
+                    let mut reached = reached;
+                    while reached < rest.len() && n == rest[reached] {
+                        reached += 1;
+                    }
+                    let s = state(n, t + 1, reached);
+                    if from[s] == u32::MAX {
+                        from[s] = here as u32;
+                        next.push((n, reached));
+                    }
                 }
             }
+            frontier = next;
         }
+        None
+    }
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Valley;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(Valley::parse(input)?)
+    }
 
-        pos = new;
-        // println!("{:?}", pos);
+    fn part1(valley: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let route = valley.plan(&[valley.start, valley.end]);
+        Ok(route.ok_or("no way through the valley")?.time)
     }
 
-    Ok(())
+    fn part2(valley: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        // back for the snacks
+        let waypoints = [valley.start, valley.end, valley.start, valley.end];
+        let route = valley.plan(&waypoints);
+        Ok(route.ok_or("no way through the valley")?.time)
+    }
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut valley = Valley::parse(input);
-
-    // the set of possible positions
-    let mut pos = HashSet::new();
-    pos.insert(valley.start);
-    let mut goal = valley.end;
-    // this is reverse order because we pop from the end
-    let mut goals_remaining = vec![valley.end, valley.start];
-
-    'label: for i in 0..1000 {
-        valley.step();
-
-        // all of the occupied spaces after the step
-        let occ: HashSet<Loc> = valley.blizzards.iter().map(|b| b.loc).collect();
-
-        let mut new = HashSet::new();
-
-        for p in pos.iter() {
-            let next = [
-                Loc::new(p.x, p.y),
-                Loc::new(p.x, p.y + 1),
-                Loc::new(p.x, p.y - 1),
-                Loc::new(p.x + 1, p.y),
-                Loc::new(p.x - 1, p.y),
-            ];
-
-            // check if we can get to the goal
-            if next.contains(&goal) {
-                if goals_remaining.is_empty() {
-                    println!("done: {}", i + 1);
-                    break 'label;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                // we got to the goal, so that is the only starting position
-                new = HashSet::new();
-                new.insert(goal);
+    #[test]
+    fn test_example() {
+        let valley = Day24::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day24::part1(&valley).unwrap(), 18);
+        assert_eq!(Day24::part2(&valley).unwrap(), 54);
+    }
 
-                // get the new goal
-                goal = goals_remaining.pop().unwrap();
+    #[test]
+    fn test_blizzards() {
+        let valley = Day24::parse(TEST_INPUT).unwrap();
+        assert_eq!(valley.period, 12);
+        assert_eq!(valley.render(0), TEST_INPUT.to_owned() + "\n");
+        assert_eq!(
+            valley.render(1),
+            "#.######\n#.>3.<.#\n#<..<<.#\n#>2.22.#\n#>v..^<#\n######.#\n"
+        );
+        assert_eq!(valley.render(12), valley.render(0));
+    }
 
-                break;
-            } else {
-                for n in next {
-                    if valley.contains(&n) && !occ.contains(&n) {
-                        new.insert(n);
-                    }
-                }
-            }
+    #[test]
+    fn test_plan() {
+        let valley = Day24::parse(TEST_INPUT).unwrap();
+        let route = valley.plan(&[valley.start, valley.end]).unwrap();
+        assert_eq!(route.time, 18);
+        assert_eq!(route.path.len(), 19);
+        assert_eq!(route.path[0], valley.start);
+        assert_eq!(route.path[18], valley.end);
+        for (t, pair) in route.path.windows(2).enumerate() {
+            assert!(pair[0].manhattan(pair[1]) <= 1);
+            assert!(valley.is_clear(pair[1], t + 1), "minute {}", t + 1);
         }
 
-        pos = new;
-        // println!("{:?}", pos);
+        // the first leg of part 2 on its own, then the way back
+        let back = valley.plan(&[valley.end, valley.start]);
+        assert!(back.is_some());
+        assert_eq!(valley.plan(&[valley.start]).map(|r| r.time), Some(0));
+        assert_eq!(
+            valley.plan(&[valley.start, valley.start]).map(|r| r.time),
+            Some(0)
+        );
+        assert_eq!(valley.plan(&[valley.start, Point::new(0, 1)]), None);
+
+        // repeating a waypoint doesn't cost anything
+        let twice = valley
+            .plan(&[valley.start, valley.end, valley.end])
+            .unwrap();
+        assert_eq!(twice.time, 18);
+        let trip = [valley.start, valley.end, valley.start, valley.end];
+        let again = [
+            valley.start,
+            valley.end,
+            valley.end,
+            valley.start,
+            valley.start,
+            valley.end,
+        ];
+        assert_eq!(
+            valley.plan(&again).map(|r| r.time),
+            valley.plan(&trip).map(|r| r.time)
+        );
     }
-    Ok(())
 }
@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use crate::util;
+use crate::{parse::ParseError, solution::Solution, util};
 
 static TEST_INPUT: &str = "1=-0-2
 12111
@@ -16,22 +16,25 @@ static TEST_INPUT: &str = "1=-0-2
 1=
 122";
 
-fn from_snafu(s: &str) -> i64 {
+fn from_snafu(s: &str) -> Result<i64, ParseError> {
     let mut p = 1;
     let mut sum = 0;
-    for c in s.chars().rev() {
+    for (i, c) in s.char_indices().rev() {
         let x = match c {
             '2' => 2,
             '1' => 1,
             '0' => 0,
             '-' => -1,
             '=' => -2,
-            _ => panic!("bad snafu: {}", c),
+            _ => {
+                let bad = &s[i..i + c.len_utf8()];
+                return Err(ParseError::new(s, bad, "expected a snafu digit"));
+            }
         };
         sum += x * p;
         p *= 5;
     }
-    sum
+    Ok(sum)
 }
 
 fn to_snafu(i: i64) -> String {
@@ -82,10 +85,28 @@ fn to_snafu(i: i64) -> String {
         .collect()
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let result: i64 = util::read_lines(input).map(from_snafu).sum();
-    println!("{}", to_snafu(result));
-    Ok(())
+pub struct Day25;
+
+impl Solution for Day25 {
+    type Input = Vec<i64>;
+    type Answer1 = String;
+    type Answer2 = String;
+
+    const PARTS: u8 = 1;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::numbered_lines(input)
+            .map(|(n, line)| from_snafu(line).map_err(|e| e.at_line(n)))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(numbers: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(to_snafu(numbers.iter().sum()))
+    }
+
+    fn part2(_: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Err("day 25 only has one part".into())
+    }
 }
 
 #[cfg(test)]
@@ -94,19 +115,19 @@ mod tests {
 
     #[test]
     fn test_from_snafu() {
-        assert_eq!(from_snafu("1=-0-2"), 1747);
-        assert_eq!(from_snafu("12111"), 906);
-        assert_eq!(from_snafu("2=0="), 198);
-        assert_eq!(from_snafu("21"), 11);
-        assert_eq!(from_snafu("2=01"), 201);
-        assert_eq!(from_snafu("111"), 31);
-        assert_eq!(from_snafu("20012"), 1257);
-        assert_eq!(from_snafu("112"), 32);
-        assert_eq!(from_snafu("1=-1="), 353);
-        assert_eq!(from_snafu("1-12"), 107);
-        assert_eq!(from_snafu("12"), 7);
-        assert_eq!(from_snafu("1="), 3);
-        assert_eq!(from_snafu("122"), 37);
+        assert_eq!(from_snafu("1=-0-2"), Ok(1747));
+        assert_eq!(from_snafu("12111"), Ok(906));
+        assert_eq!(from_snafu("2=0="), Ok(198));
+        assert_eq!(from_snafu("21"), Ok(11));
+        assert_eq!(from_snafu("2=01"), Ok(201));
+        assert_eq!(from_snafu("111"), Ok(31));
+        assert_eq!(from_snafu("20012"), Ok(1257));
+        assert_eq!(from_snafu("112"), Ok(32));
+        assert_eq!(from_snafu("1=-1="), Ok(353));
+        assert_eq!(from_snafu("1-12"), Ok(107));
+        assert_eq!(from_snafu("12"), Ok(7));
+        assert_eq!(from_snafu("1="), Ok(3));
+        assert_eq!(from_snafu("122"), Ok(37));
     }
 
     #[test]
@@ -125,4 +146,10 @@ mod tests {
         assert_eq!("1=", to_snafu(3));
         assert_eq!("122", to_snafu(37));
     }
+
+    #[test]
+    fn test_example() {
+        let numbers = Day25::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day25::part1(&numbers).unwrap(), "2=-1=0");
+    }
 }
@@ -1,69 +1,92 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, error::Error};
 
-use crate::util;
+use crate::{parse::ParseError, solution::Solution, util};
+
+static TEST_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
 
 fn find_duplicate_char(s: &str) -> Option<char> {
     let cs: Vec<char> = s.chars().collect();
     let size = cs.len() / 2;
 
-    let first: HashSet<char> = cs[..size].iter().map(|c| *c).collect();
-    let second: HashSet<char> = cs[size..].iter().map(|c| *c).collect();
+    let first: HashSet<char> = cs[..size].iter().copied().collect();
+    let second: HashSet<char> = cs[size..].iter().copied().collect();
 
-    first.intersection(&second).next().map(|c| *c)
+    first.intersection(&second).next().copied()
 }
 
 fn score(c: &char) -> u32 {
-    if *c >= 'a' && *c <= 'z' {
+    if c.is_ascii_lowercase() {
         return *c as u32 - 'a' as u32 + 1;
     }
-    if *c >= 'A' && *c <= 'Z' {
+    if c.is_ascii_uppercase() {
         return *c as u32 - 'A' as u32 + 27;
     }
-    return 0;
+    0
 }
 
-pub fn part1(input: &str) -> u32 {
-    let dups: Vec<char> = util::read_lines(input)
-        .map(|line| find_duplicate_char(line).expect("missing duplicate"))
-        .collect();
-
-    println!("dups: {:?}", dups);
-
-    let scores: Vec<u32> = dups.iter().map(|c| score(c)).collect();
-
-    println!("scores: {:?}", scores);
-
-    let sum = scores.iter().sum();
-
-    println!("sum: {}", sum);
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Vec<String>;
+    type Answer1 = u32;
+    type Answer2 = u32;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::numbered_lines(input)
+            .map(
+                |(n, line)| match line.find(|c: char| !c.is_ascii_alphabetic()) {
+                    Some(i) => {
+                        Err(ParseError::new(line, &line[i..=i], "expected a letter").at_line(n))
+                    }
+                    None => Ok(line.to_owned()),
+                },
+            )
+            .collect::<Result<_, _>>()?)
+    }
 
-    sum
-}
+    fn part1(lines: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let dups: Vec<char> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                find_duplicate_char(line)
+                    .ok_or_else(|| format!("rucksack {} has no item in both compartments", i + 1))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(dups.iter().map(score).sum())
+    }
 
-pub fn part2(input: &str) {
-    let lines: Vec<&str> = util::read_lines(input).collect();
+    fn part2(lines: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let groups = lines.chunks_exact(3);
+        if !groups.remainder().is_empty() {
+            return Err(format!("{} rucksacks don't make whole groups of 3", lines.len()).into());
+        }
 
-    let mut scores = Vec::new();
-    for i in 0..lines.len() / 3 {
-        let start = i * 3;
+        let mut scores = Vec::new();
+        for (i, group) in groups.enumerate() {
+            let one: HashSet<char> = group[0].chars().collect();
+            let two: HashSet<char> = group[1].chars().collect();
+            let three: HashSet<char> = group[2].chars().collect();
 
-        let one: HashSet<char> = lines[start].chars().collect();
-        let two: HashSet<char> = lines[start + 1].chars().collect();
-        let three: HashSet<char> = lines[start + 2].chars().collect();
+            let i1: HashSet<char> = one.intersection(&two).copied().collect();
 
-        let i1: HashSet<char> = one.intersection(&two).map(|c| *c).collect();
+            let i2 = three
+                .intersection(&i1)
+                .next()
+                .map(score)
+                .ok_or_else(|| format!("group {} has no badge in common", i + 1))?;
 
-        let i2 = three
-            .intersection(&i1)
-            .next()
-            .map(|c| score(c))
-            .expect("one intersection");
+            scores.push(i2);
+        }
 
-        scores.push(i2);
+        Ok(scores.iter().sum())
     }
-
-    let sum: u32 = scores.iter().sum();
-    println!("sum: {}", sum);
 }
 
 #[cfg(test)]
@@ -78,13 +101,32 @@ mod tests {
 
     #[test]
     fn part1_test() {
-        let input = "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw";
+        let lines = Day3::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day3::part1(&lines).unwrap(), 157)
+    }
+
+    #[test]
+    fn test_example() {
+        let lines = Day3::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day3::part1(&lines).unwrap(), 157);
+        assert_eq!(Day3::part2(&lines).unwrap(), 70);
+    }
 
-        assert_eq!(part1(input), 157)
+    #[test]
+    fn test_errors() {
+        let lines = Day3::parse("abcd\nabca\nbcda\nxyzx").unwrap();
+        assert_eq!(
+            Day3::part1(&lines).unwrap_err().to_string(),
+            "rucksack 1 has no item in both compartments"
+        );
+        assert_eq!(
+            Day3::part2(&lines).unwrap_err().to_string(),
+            "4 rucksacks don't make whole groups of 3"
+        );
+        let lines = Day3::parse("abab\nbcbc\ncdcd").unwrap();
+        assert_eq!(
+            Day3::part2(&lines).unwrap_err().to_string(),
+            "group 1 has no badge in common"
+        );
     }
 }
@@ -1,71 +1,92 @@
-use crate::util;
+use std::{error::Error, str::FromStr};
 
-struct Assignment {
-    lo: u32,
-    hi: u32,
-}
+use crate::{
+    interval::Interval,
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
-impl From<&str> for Assignment {
-    fn from(s: &str) -> Self {
-        let v: Vec<&str> = s.split('-').collect();
-        if v.len() != 2 {
-            panic!("expected 2 bits, {}", s)
-        }
-        Assignment {
-            lo: v[0].parse().expect("bad int"),
-            hi: v[1].parse().expect("bad int"),
-        }
-    }
-}
+static TEST_INPUT: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8";
 
-impl Assignment {
-    fn contains(&self, other: &Self) -> bool {
-        self.lo <= other.lo && self.hi >= other.hi
+// e.g. 2-4, `line` is only used for the error position
+fn parse_assignment(line: &str, s: &str) -> Result<Interval, ParseError> {
+    let (lo, hi) = s
+        .split_once('-')
+        .ok_or_else(|| ParseError::new(line, s, "expected a range like 2-4"))?;
+    let (lo, hi) = (parse::number(line, lo)?, parse::number(line, hi)?);
+    if lo > hi {
+        return Err(ParseError::new(
+            line,
+            s,
+            "expected the start before the end",
+        ));
     }
+    Ok(Interval::new(lo, hi))
 }
 
-struct Pair {
-    a1: Assignment,
-    a2: Assignment,
+pub struct Pair {
+    a1: Interval,
+    a2: Interval,
 }
 
-impl From<&str> for Pair {
-    fn from(s: &str) -> Self {
-        let v: Vec<&str> = s.split(',').collect();
-        if v.len() != 2 {
-            panic!("expected 2 bits, {}", s)
-        }
-        Pair {
-            a1: v[0].into(),
-            a2: v[1].into(),
-        }
+impl FromStr for Pair {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a1, a2) = s
+            .split_once(',')
+            .ok_or_else(|| ParseError::missing(s, "expected 2 ranges"))?;
+        Ok(Pair {
+            a1: parse_assignment(s, a1)?,
+            a2: parse_assignment(s, a2)?,
+        })
     }
 }
 
 impl Pair {
     fn one_contained(&self) -> bool {
-        self.a1.contains(&self.a2) || self.a2.contains(&self.a1)
+        self.a1.contains_interval(&self.a2) || self.a2.contains_interval(&self.a1)
     }
 
     fn overlap(&self) -> bool {
-        self.a1.hi >= self.a2.lo && self.a2.hi >= self.a1.lo
+        self.a1.overlaps(&self.a2)
     }
 }
 
-pub fn part1(input: &str) {
-    let count = util::read_lines(input)
-        .map(|s| Pair::from(s))
-        .filter(|p| p.one_contained())
-        .count();
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Pair>;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    println!("count: {}", count);
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::parse_lines(input)?)
+    }
+
+    fn part1(pairs: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(pairs.iter().filter(|p| p.one_contained()).count())
+    }
+
+    fn part2(pairs: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(pairs.iter().filter(|p| p.overlap()).count())
+    }
 }
 
-pub fn part2(input: &str) {
-    let count = util::read_lines(input)
-        .map(|s| Pair::from(s))
-        .filter(|p| p.overlap())
-        .count();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("count: {}", count);
+    #[test]
+    fn test_example() {
+        let pairs = Day4::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day4::part1(&pairs).unwrap(), 2);
+        assert_eq!(Day4::part2(&pairs).unwrap(), 4);
+    }
 }
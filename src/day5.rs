@@ -1,88 +1,231 @@
-use std::error::Error;
-
-use crate::util;
-
-pub fn part1(input: &str) {
-    let mut stacks = vec![
-        vec![],
-        vec!['N', 'C', 'R', 'T', 'M', 'Z', 'P'],
-        vec!['D', 'N', 'T', 'S', 'B', 'Z'],
-        vec!['M', 'H', 'Q', 'R', 'F', 'C', 'T', 'G'],
-        vec!['G', 'R', 'Z'],
-        vec!['Z', 'N', 'R', 'H'],
-        vec!['F', 'H', 'S', 'W', 'P', 'Z', 'L', 'D'],
-        vec!['W', 'D', 'Z', 'R', 'C', 'G', 'M'],
-        vec!['S', 'J', 'F', 'L', 'H', 'W', 'Z', 'Q'],
-        vec!['S', 'Q', 'P', 'W', 'N'],
-    ];
-
-    let mut moves = false;
-    for line in util::read_lines(input) {
-        if moves {
-            let c: Vec<_> = line.split(' ').collect();
-            let count: usize = c[1].parse().expect("bad int");
-            let from: usize = c[3].parse().expect("bad int");
-            let to: usize = c[5].parse().expect("bad int");
-
-            for _ in 0..count {
-                let x = stacks[from].pop().expect("empty stack!!");
-                stacks[to].push(x);
+use std::{error::Error, str::FromStr};
+
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
+
+static TEST_INPUT: &str = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Move {
+    count: usize,
+    from: usize,
+    to: usize,
+}
+
+pub struct Supplies {
+    // stacks are numbered from 1, so index 0 is always empty
+    stacks: Vec<Vec<char>>,
+    moves: Vec<Move>,
+}
+
+// e.g.
+//     [D]
+// [N] [C]
+// [Z] [M] [P]
+//  1   2   3
+fn parse_stacks(drawing: &str) -> Result<Vec<Vec<char>>, ParseError> {
+    let mut lines: Vec<_> = drawing.lines().enumerate().collect();
+    let (_, numbers) = lines
+        .pop()
+        .ok_or_else(|| ParseError::missing(drawing, "expected a drawing of the stacks"))?;
+    let count = numbers.split_ascii_whitespace().count();
+
+    let mut stacks = vec![vec![]; count + 1];
+    // build the stacks from the bottom up
+    for (n, line) in lines.into_iter().rev() {
+        for (i, stack) in stacks.iter_mut().enumerate().skip(1) {
+            let col = 4 * i - 3;
+            match line.get(col..=col) {
+                Some(" ") | None => {}
+                Some(c) if c.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    stack.push(c.chars().next().unwrap())
+                }
+                Some(c) => return Err(ParseError::new(line, c, "expected a crate").at_line(n + 1)),
             }
         }
+    }
 
-        if line == "" {
-            moves = true;
+    Ok(stacks)
+}
+
+// e.g. move 1 from 2 to 1
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        for (i, word) in [(0, "move"), (2, "from"), (4, "to")] {
+            let t = parse::token(line, i, word)?;
+            if t != word {
+                return Err(ParseError::new(line, t, format!("expected {:?}", word)));
+            }
         }
+        Ok(Move {
+            count: parse::number(line, parse::token(line, 1, "a count")?)?,
+            from: parse::number(line, parse::token(line, 3, "a stack")?)?,
+            to: parse::number(line, parse::token(line, 5, "a stack")?)?,
+        })
     }
+}
 
-    for i in 1..stacks.len() {
-        print!("{}", stacks[i].last().unwrap())
+impl Move {
+    // the stacks have to be in the drawing, which has `stacks` of them
+    fn check(self, line: &str, stacks: usize) -> Result<Self, ParseError> {
+        for (i, n) in [(3, self.from), (5, self.to)] {
+            if !(1..=stacks).contains(&n) {
+                let t = parse::token(line, i, "a stack")?;
+                let msg = format!("expected a stack from 1 to {}", stacks);
+                return Err(ParseError::new(line, t, msg));
+            }
+        }
+        Ok(self)
     }
-    println!("")
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let mut stacks = vec![
-        vec![],
-        vec!['N', 'C', 'R', 'T', 'M', 'Z', 'P'],
-        vec!['D', 'N', 'T', 'S', 'B', 'Z'],
-        vec!['M', 'H', 'Q', 'R', 'F', 'C', 'T', 'G'],
-        vec!['G', 'R', 'Z'],
-        vec!['Z', 'N', 'R', 'H'],
-        vec!['F', 'H', 'S', 'W', 'P', 'Z', 'L', 'D'],
-        vec!['W', 'D', 'Z', 'R', 'C', 'G', 'M'],
-        vec!['S', 'J', 'F', 'L', 'H', 'W', 'Z', 'Q'],
-        vec!['S', 'Q', 'P', 'W', 'N'],
-    ];
-
-    let mut moves = false;
-    for line in util::read_lines(input) {
-        if moves {
-            let c: Vec<_> = line.split(' ').collect();
-            let count: usize = c[1].parse()?;
-            let from: usize = c[3].parse()?;
-            let to: usize = c[5].parse()?;
+fn pop(stacks: &mut [Vec<char>], from: usize) -> Result<char, String> {
+    stacks[from]
+        .pop()
+        .ok_or_else(|| format!("stack {} is empty", from))
+}
+
+fn tops(stacks: &[Vec<char>]) -> Result<String, String> {
+    stacks[1..]
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            s.last()
+                .copied()
+                .ok_or_else(|| format!("stack {} is empty", i + 1))
+        })
+        .collect()
+}
+
+pub struct Day5;
 
+impl Solution for Day5 {
+    type Input = Supplies;
+    type Answer1 = String;
+    type Answer2 = String;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        // the drawing is space aligned, so only trim the newlines
+        let trimmed = input.trim_start_matches('\n');
+        let skipped = input.len() - trimmed.len();
+        let input = trimmed.trim_end();
+
+        let (drawing, moves) = input
+            .split_once("\n\n")
+            .ok_or_else(|| ParseError::missing(input, "expected a blank line after the drawing"))?;
+        let stacks = parse_stacks(drawing).map_err(|e| e.offset_lines(skipped))?;
+        let offset = skipped + drawing.lines().count() + 1;
+        let moves = util::numbered_lines(moves)
+            .map(|(n, line)| {
+                line.parse()
+                    .and_then(|m: Move| m.check(line, stacks.len() - 1))
+                    .map_err(|e| e.at_line(n).offset_lines(offset))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Supplies { stacks, moves })
+    }
+
+    fn part1(supplies: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        let mut stacks = supplies.stacks.clone();
+
+        for m in supplies.moves.iter() {
+            for _ in 0..m.count {
+                let x = pop(&mut stacks, m.from)?;
+                stacks[m.to].push(x);
+            }
+        }
+
+        Ok(tops(&stacks)?)
+    }
+
+    fn part2(supplies: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let mut stacks = supplies.stacks.clone();
+
+        for m in supplies.moves.iter() {
             let mut tmp = Vec::new();
-            for _ in 0..count {
-                let x = stacks[from].pop().unwrap();
-                tmp.push(x);
+            for _ in 0..m.count {
+                tmp.push(pop(&mut stacks, m.from)?);
             }
 
-            for _ in 0..count {
-                stacks[to].push(tmp.pop().unwrap())
+            for _ in 0..m.count {
+                stacks[m.to].push(tmp.pop().unwrap())
             }
         }
 
-        if line == "" {
-            moves = true;
+        Ok(tops(&stacks)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let supplies = Day5::parse(TEST_INPUT).unwrap();
+        assert_eq!(
+            supplies.stacks,
+            vec![vec![], vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+        );
+        assert_eq!(supplies.moves.len(), 4);
+        assert_eq!(Day5::part1(&supplies).unwrap(), "CMZ");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = TEST_INPUT.replace("from 1 to 3", "from one to 3");
+        let err = Day5::parse(&input).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 7, column 13: expected a number, found \"one\""
+        );
+
+        for (change, column) in [("from 9 to 1", 13), ("from 2 to 9", 18)] {
+            let input = TEST_INPUT.replacen("from 2 to 1", change, 1);
+            let err = Day5::parse(&input).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "line 6, column {}: expected a stack from 1 to 3, found \"9\"",
+                    column
+                )
+            );
         }
+        let input = TEST_INPUT.replace("from 2 to 1", "from 0 to 1");
+        assert!(Day5::parse(&input).is_err());
     }
 
-    for i in 1..stacks.len() {
-        print!("{}", stacks[i].last().unwrap())
+    #[test]
+    fn test_empty_stack() {
+        let input = TEST_INPUT.replace("move 3 from 1", "move 4 from 1");
+        let supplies = Day5::parse(&input).unwrap();
+        let err = Day5::part1(&supplies).unwrap_err();
+        assert_eq!(err.to_string(), "stack 1 is empty");
+        // nothing left on top of stack 1
+        let input = TEST_INPUT.replace("move 1 from 1 to 2", "move 2 from 1 to 2");
+        let supplies = Day5::parse(&input).unwrap();
+        let err = Day5::part2(&supplies).unwrap_err();
+        assert_eq!(err.to_string(), "stack 1 is empty");
     }
-    println!("");
 
-    Ok(())
+    #[test]
+    fn test_example() {
+        let supplies = Day5::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day5::part1(&supplies).unwrap(), "CMZ");
+        assert_eq!(Day5::part2(&supplies).unwrap(), "MCD");
+    }
 }
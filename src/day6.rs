@@ -1,7 +1,7 @@
-extern crate test;
-
 use std::{collections::HashSet, error::Error};
 
+use crate::solution::Solution;
+
 fn distinct(w: &[char]) -> bool {
     let mut hs = HashSet::new();
     for c in w {
@@ -27,9 +27,9 @@ pub fn find_repeat2(b: &[u8], size: usize) -> Option<usize> {
     let mut tracker = [0u8; 256];
     let mut dups = 0;
 
-    for i in 0..b.len() {
+    for (i, &c) in b.iter().enumerate() {
         // handle the incoming char
-        let c = b[i] as usize;
+        let c = c as usize;
         if tracker[c] == 1 {
             // we are about to add a duplicate
             dups += 1;
@@ -56,24 +56,29 @@ pub fn find_repeat2(b: &[u8], size: usize) -> Option<usize> {
     None
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let x = find_repeat2(input.as_bytes(), 4);
-    println!("x: {:?}", x);
-    Ok(())
-}
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = String;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(input.trim().to_owned())
+    }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let x = find_repeat2(input.as_bytes(), 14);
-    println!("x: {:?}", x);
-    Ok(())
+    fn part1(input: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        find_repeat2(input.as_bytes(), 4).ok_or_else(|| "no start-of-packet marker".into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        find_repeat2(input.as_bytes(), 14).ok_or_else(|| "no start-of-message marker".into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-
     use super::*;
-    use test::Bencher;
 
     #[test]
     fn test() {
@@ -88,42 +93,18 @@ mod tests {
         );
     }
 
-    #[bench]
-    fn bench_find_repeat_4(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        b.iter(|| find_repeat(&input, 4))
-    }
-
-    #[bench]
-    fn bench_find_repeat_8(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        b.iter(|| find_repeat(&input, 18))
-    }
-
-    #[bench]
-    fn bench_find_repeat_16(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        b.iter(|| find_repeat(&input, 16))
-    }
-
-    #[bench]
-    fn bench_find_repeat2_4(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        let bytes = input.as_bytes();
-        b.iter(|| find_repeat2(bytes, 4))
-    }
-
-    #[bench]
-    fn bench_find_repeat2_8(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        let bytes = input.as_bytes();
-        b.iter(|| find_repeat2(bytes, 8))
-    }
-
-    #[bench]
-    fn bench_find_repeat2_16(b: &mut Bencher) {
-        let input = fs::read_to_string("../day6.txt").unwrap();
-        let bytes = input.as_bytes();
-        b.iter(|| find_repeat2(bytes, 16))
+    #[test]
+    fn test_example() {
+        for (input, start, message) in [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ] {
+            let input = Day6::parse(input).unwrap();
+            assert_eq!(Day6::part1(&input).unwrap(), start);
+            assert_eq!(Day6::part2(&input).unwrap(), message);
+        }
     }
 }
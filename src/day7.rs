@@ -1,6 +1,10 @@
 use std::{collections::HashMap, error::Error};
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "$ cd /
 $ ls
@@ -26,7 +30,7 @@ $ ls
 5626152 d.ext
 7214296 k";
 
-struct Dir {
+pub struct Dir {
     id: usize,
     parent: Option<usize>,
     name: String,
@@ -48,7 +52,7 @@ impl Dir {
     }
 
     fn find_sub_dir(&self, name: &str) -> Option<usize> {
-        self.dirs.get(name).map(|x| *x)
+        self.dirs.get(name).copied()
     }
 
     fn add_dir(&mut self, name: &str, id: usize) {
@@ -90,74 +94,95 @@ fn dump(id: usize, fs: &[Dir], indent: usize) {
     }
 }
 
-fn parse(input: &str) -> Vec<Dir> {
-    let mut fs = Vec::new();
+fn parse_line(line: &str, cwd: &mut usize, fs: &mut Vec<Dir>) -> Result<(), ParseError> {
+    match line {
+        "$ ls" => {}
+        "$ cd /" => {
+            *cwd = 0;
+        }
+        "$ cd .." => {
+            *cwd = fs[*cwd]
+                .parent
+                .ok_or_else(|| ParseError::new(line, &line[5..], "cd .. with no parent"))?;
+        }
+        c if c.starts_with("$ cd ") => {
+            let name = &c[5..];
+
+            *cwd = fs[*cwd]
+                .find_sub_dir(name)
+                .ok_or_else(|| ParseError::new(line, name, "unknown subdir"))?;
+        }
+        c if c.starts_with('$') => {
+            return Err(ParseError::new(line, line, "unknown command"));
+        }
+        // a dir
+        c if c.starts_with("dir ") => {
+            let name = &c[4..];
+
+            let id = fs.len();
+            fs[*cwd].add_dir(name, id);
+            let dir = Dir::new(id, Some(*cwd), name);
+            fs.push(dir);
+        }
+        // a file
+        _ => {
+            let (size, name) = line
+                .split_once(' ')
+                .ok_or_else(|| ParseError::missing(line, "expected a file name"))?;
+            fs[*cwd].add_file(name, parse::number(line, size)?);
+        }
+    }
+    Ok(())
+}
+
+fn parse(input: &str) -> Result<Vec<Dir>, ParseError> {
     // add root
-    fs.push(Dir::new(0, None, "/"));
+    let mut fs = vec![Dir::new(0, None, "/")];
 
     let mut cwd = 0;
 
-    for line in util::read_lines(input) {
-        match line {
-            "$ ls" => {}
-            "$ cd /" => {
-                cwd = 0;
-            }
-            "$ cd .." => {
-                cwd = fs[cwd].parent.expect("cd .. with no parent");
-            }
-            c if c.starts_with("$ cd") => {
-                let x: Vec<_> = c.split(" ").collect();
-                let name = x[2];
-
-                cwd = fs[cwd].find_sub_dir(name).expect("subdir missing");
-            }
-            // a dir
-            c if c.starts_with("dir") => {
-                let x: Vec<_> = c.split(" ").collect();
-                let name = x[1];
-
-                let id = fs.len();
-                fs[cwd].add_dir(name, id);
-                let dir = Dir::new(id, Some(cwd), name);
-                fs.push(dir);
-            }
-            // a file
-            _ => {
-                let x: Vec<_> = line.split(" ").collect();
-                fs[cwd].add_file(x[1], x[0].parse().expect("bad int"));
-            }
-        }
+    for (n, line) in util::numbered_lines(input) {
+        parse_line(line, &mut cwd, &mut fs).map_err(|e| e.at_line(n))?;
     }
-    fs
+    Ok(fs)
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let fs = parse(input);
-    // dump(0, &fs, 0);
-    let size: usize = fs
-        .iter()
-        .map(|dir| dir.total_size(&fs))
-        .filter(|size| *size <= 100000)
-        .sum();
-    println!("{:?}", size);
-    Ok(())
-}
+pub struct Day7;
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let fs = parse(input);
-    let total_size = fs[0].total_size(&fs);
-    let required = 30000000 - (70000000 - total_size);
-    println!("required: {}", required);
+impl Solution for Day7 {
+    type Input = Vec<Dir>;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    let size = fs
-        .iter()
-        .map(|dir| dir.total_size(&fs))
-        .filter(|size| *size >= required)
-        .min();
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(parse(input)?)
+    }
 
-    println!("min: {:?}", size);
-    Ok(())
+    fn part1(fs: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        // dump(0, &fs, 0);
+        Ok(fs
+            .iter()
+            .map(|dir| dir.total_size(fs))
+            .filter(|size| *size <= 100000)
+            .sum())
+    }
+
+    fn part2(fs: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        let total_size = fs[0].total_size(fs);
+        let free = 70000000usize
+            .checked_sub(total_size)
+            .ok_or_else(|| format!("the disk is over capacity, {} is used", total_size))?;
+        // already enough space without deleting anything
+        let Some(required) = 30000000usize.checked_sub(free) else {
+            return Ok(0);
+        };
+
+        fs.iter()
+            .map(|dir| dir.total_size(fs))
+            .filter(|size| *size >= required)
+            .min()
+            .ok_or_else(|| "no directory is big enough".into())
+    }
 }
 
 #[cfg(test)]
@@ -166,7 +191,25 @@ mod tests {
 
     #[test]
     fn test() {
-        let fs = parse(TEST_INPUT);
+        let fs = parse(TEST_INPUT).unwrap();
         assert_eq!(fs.len(), 4);
     }
+
+    #[test]
+    fn test_example() {
+        let fs = Day7::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day7::part1(&fs).unwrap(), 95437);
+        assert_eq!(Day7::part2(&fs).unwrap(), 24933642);
+    }
+
+    #[test]
+    fn test_disk_space() {
+        let fs = Day7::parse("$ cd /\n$ ls\n100 a").unwrap();
+        assert_eq!(Day7::part2(&fs).unwrap(), 0);
+        let fs = Day7::parse("$ cd /\n$ ls\n70000001 a").unwrap();
+        assert_eq!(
+            Day7::part2(&fs).unwrap_err().to_string(),
+            "the disk is over capacity, 70000001 is used"
+        );
+    }
 }
@@ -1,6 +1,10 @@
 use std::error::Error;
 
-use crate::util;
+use crate::{
+    grid::{Grid, Point},
+    parse::ParseError,
+    solution::Solution,
+};
 
 static TEST_INPUT: &str = "30373
 25512
@@ -8,41 +12,15 @@ static TEST_INPUT: &str = "30373
 33549
 35390";
 
-fn parse_input(input: &str) -> Vec<Vec<u8>> {
-    util::read_lines(input)
-        .map(|line| {
-            line.chars()
-                .map(|c| c.to_string().parse().unwrap())
-                .collect()
-        })
-        .collect()
+fn parse_input(input: &str) -> Result<Grid<u8>, ParseError> {
+    Grid::parse(input, "a digit", |c| c.to_digit(10).map(|d| d as u8))
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    let grid = parse_input(input);
-
-    // minus 4 because all corners are counted twice
-    let mut visible = 2 * grid.len() + 2 * grid[0].len() - 4;
-
-    // take one from the lengths because the edge is visible
-    for r in 1..grid.len() - 1 {
-        let row = &grid[r];
-        for c in 1..row.len() - 1 {
-            let h = row[c];
-
-            // get the visibility lines
-            let left = *row[..c].iter().max().unwrap();
-            let right = *row[c + 1..].iter().max().unwrap();
-            let up = grid[..r].iter().map(|x| x[c]).max().unwrap();
-            let down = grid[r + 1..].iter().map(|x| x[c]).max().unwrap();
-
-            if h > left || h > right || h > up || h > down {
-                visible += 1;
-            }
-        }
-    }
-    println!("visible: {}", visible);
-    Ok(())
+// the trees in a line from `p` (not included) to the edge
+fn line_of_sight(grid: &Grid<u8>, p: Point, dir: Point) -> impl Iterator<Item = u8> + '_ {
+    (1..)
+        .map(move |i| p + dir * i)
+        .map_while(|l| grid.get(l).copied())
 }
 
 fn count_trees(h: u8, trees: impl Iterator<Item = u8>) -> u32 {
@@ -56,42 +34,56 @@ fn count_trees(h: u8, trees: impl Iterator<Item = u8>) -> u32 {
     count
 }
 
-fn tree_count(grid: &Vec<Vec<u8>>, r: usize, c: usize) -> [u32; 4] {
-    let row = &grid[r];
-    let h = row[c];
-
-    // get the visibility lines
-    let left = count_trees(h, row[..c].iter().rev().map(|&x| x));
-    let right = if c == row.len() {
-        0
-    } else {
-        count_trees(h, row[c + 1..].iter().map(|&x| x))
-    };
-    let up = count_trees(h, grid[..r].iter().map(|x| x[c]).rev());
-    let down = if r == grid.len() {
-        0
-    } else {
-        count_trees(h, grid[r + 1..].iter().map(|x| x[c]))
-    };
+fn scenic_score(grid: &Grid<u8>, p: Point) -> u32 {
+    let h = grid[p];
+    Point::ORTHOGONAL
+        .into_iter()
+        .map(|dir| count_trees(h, line_of_sight(grid, p, dir)))
+        .product()
+}
 
-    [left, right, up, down]
+fn visible(grid: &Grid<u8>) -> usize {
+    grid.iter()
+        .filter(|&(p, &h)| {
+            Point::ORTHOGONAL
+                .into_iter()
+                .any(|dir| line_of_sight(grid, p, dir).all(|t| t < h))
+        })
+        .count()
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    let grid = parse_input(input);
+pub struct Day8;
 
-    let mut max_score = 0;
+impl Solution for Day8 {
+    type Input = Grid<u8>;
+    type Answer1 = usize;
+    type Answer2 = u32;
 
-    for r in 0..grid.len() {
-        for c in 0..grid[r].len() {
-            let x = tree_count(&grid, r, c);
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(parse_input(input)?)
+    }
 
-            let score: u32 = x.iter().product();
-            if score > max_score {
-                max_score = score
-            }
-        }
+    fn part1(grid: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(visible(grid))
+    }
+
+    fn part2(grid: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(grid
+            .points()
+            .map(|p| scenic_score(grid, p))
+            .max()
+            .unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let grid = Day8::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day8::part1(&grid).unwrap(), 21);
+        assert_eq!(Day8::part2(&grid).unwrap(), 8);
     }
-    println!("max_score: {}", max_score);
-    Ok(())
 }
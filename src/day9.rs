@@ -1,6 +1,10 @@
 use std::{collections::HashSet, error::Error};
 
-use crate::util;
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+    util,
+};
 
 static TEST_INPUT: &str = "R 4
 U 4
@@ -41,22 +45,17 @@ impl Point {
     }
 }
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
+fn tail_positions(moves: &[(String, usize)]) -> usize {
     let mut h = Point::new(0, 0);
     let mut t = Point::new(0, 0);
 
     let mut set = HashSet::new();
     set.insert(t.clone());
 
-    for line in util::read_lines(input) {
-        let (d, x): (&str, usize) = line
-            .split_once(' ')
-            .map(|(x, y)| (x, y.parse().unwrap()))
-            .unwrap();
-
-        for _ in 0..x {
+    for (d, x) in moves {
+        for _ in 0..*x {
             // move the head
-            match d {
+            match d.as_str() {
                 "U" => h.add(0, 1),
                 "D" => h.add(0, -1),
                 "R" => h.add(1, 0),
@@ -66,7 +65,7 @@ pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
 
             // move the tail
             match t.diff(&h) {
-                (-1 | 0 | 1, -1 | 0 | 1) => {} // noop
+                (-1..=1, -1..=1) => {} // noop
                 (2, y) => t.add(1, y),
                 (-2, y) => t.add(-1, y),
                 (x, 2) => t.add(x, 1),
@@ -77,11 +76,10 @@ pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
             set.insert(t.clone());
         }
     }
-    println!("len: {}", set.len());
-    Ok(())
+    set.len()
 }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
+fn long_tail_positions(moves: &[(String, usize)]) -> usize {
     let mut rope = Vec::new();
     for _ in 0..10 {
         rope.push(Point::new(0, 0));
@@ -90,15 +88,10 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
     let mut set = HashSet::new();
     set.insert(rope[9].clone());
 
-    for line in util::read_lines(input) {
-        let (d, x): (&str, usize) = line
-            .split_once(' ')
-            .map(|(x, y)| (x, y.parse().unwrap()))
-            .unwrap();
-
-        for _ in 0..x {
+    for (d, x) in moves {
+        for _ in 0..*x {
             // move the head
-            match d {
+            match d.as_str() {
                 "U" => rope[0].add(0, 1),
                 "D" => rope[0].add(0, -1),
                 "R" => rope[0].add(1, 0),
@@ -109,7 +102,7 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
             // move the tail
             for i in 1..10 {
                 match rope[i].diff(&rope[i - 1]) {
-                    (-1 | 0 | 1, -1 | 0 | 1) => {} // noop
+                    (-1..=1, -1..=1) => {} // noop
                     // this seems a special case? not covered?
                     (2, 2) => rope[i].add(1, 1),
                     (2, -2) => rope[i].add(1, -1),
@@ -129,8 +122,54 @@ pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
 
             set.insert(rope[9].clone());
         }
-        // println!("{:?}", rope);
     }
-    println!("len: {}", set.len());
-    Ok(())
+    set.len()
+}
+
+// e.g. R 4
+fn parse_move(line: &str) -> Result<(String, usize), ParseError> {
+    let (d, x) = line
+        .split_once(' ')
+        .ok_or_else(|| ParseError::missing(line, "expected a distance"))?;
+    if !matches!(d, "U" | "D" | "L" | "R") {
+        return Err(ParseError::new(line, d, "expected U, D, L or R"));
+    }
+    Ok((d.to_owned(), parse::number(line, x)?))
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<(String, usize)>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        Ok(util::numbered_lines(input)
+            .map(|(n, line)| parse_move(line).map_err(|e| e.at_line(n)))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(moves: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(tail_positions(moves))
+    }
+
+    fn part2(moves: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(long_tail_positions(moves))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let moves = Day9::parse(TEST_INPUT).unwrap();
+        assert_eq!(Day9::part1(&moves).unwrap(), 13);
+        assert_eq!(Day9::part2(&moves).unwrap(), 1);
+
+        let moves = Day9::parse(TEST_INPUT2).unwrap();
+        assert_eq!(Day9::part2(&moves).unwrap(), 36);
+    }
 }
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NodeId(usize);
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -17,6 +18,30 @@ impl Edge {
     }
 }
 
+// a route through the graph, `nodes` includes both ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<T> {
+    pub cost: usize,
+    pub nodes: Vec<T>,
+}
+
+// the cheapest cost between every pair of nodes, indexed by NodeId
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Distances {
+    size: usize,
+    dist: Vec<Option<usize>>,
+}
+
+impl Distances {
+    pub fn get(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        self.dist[from.0 * self.size + to.0]
+    }
+
+    fn set(&mut self, from: NodeId, to: NodeId, cost: usize) {
+        self.dist[from.0 * self.size + to.0] = Some(cost);
+    }
+}
+
 pub struct Graph<T: Hash + Eq> {
     data: Vec<T>,
     nodes: HashMap<T, NodeId>,
@@ -66,6 +91,14 @@ impl<T: Hash + Eq + Clone> Graph<T> {
         self.add_node_edge(n2, n1, cost);
     }
 
+    pub fn node_id(&self, id: &T) -> Option<NodeId> {
+        self.nodes.get(id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
     pub fn node_data(&self, n: &NodeId) -> &T {
         &self.data[n.0]
     }
@@ -82,10 +115,139 @@ impl<T: Hash + Eq + Clone> Graph<T> {
     }
 
     pub fn shortest_path(&self, start: &T, end: &T) -> Option<usize> {
-        match (self.nodes.get(start), self.nodes.get(end)) {
-            (Some(s), Some(e)) => ShortestPath::find(self, s, e),
-            _ => None,
+        self.dijkstra(start, end).map(|p| p.cost)
+    }
+
+    pub fn dijkstra(&self, start: &T, end: &T) -> Option<Path<T>> {
+        self.astar(start, end, |_| 0)
+    }
+
+    // `heuristic` estimates the cost from a node to `end`, it must never
+    // overestimate or the path found may not be the cheapest
+    pub fn astar<H: Fn(&T) -> usize>(&self, start: &T, end: &T, heuristic: H) -> Option<Path<T>> {
+        let (&start, &end) = (self.nodes.get(start)?, self.nodes.get(end)?);
+
+        let mut costs = HashMap::from([(start, 0)]);
+        let mut prev = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((heuristic(self.node_data(&start)), 0, start)));
+
+        while let Some(Reverse((_, cost, node))) = queue.pop() {
+            if node == end {
+                return Some(self.path(&prev, end, cost));
+            }
+            // already reached more cheaply
+            if costs.get(&node).is_some_and(|&c| c < cost) {
+                continue;
+            }
+
+            for edge in self.possible(node) {
+                let cost = cost + edge.cost;
+                if costs.get(&edge.to).is_none_or(|&c| cost < c) {
+                    costs.insert(edge.to, cost);
+                    prev.insert(edge.to, node);
+                    let estimate = cost + heuristic(self.node_data(&edge.to));
+                    queue.push(Reverse((estimate, cost, edge.to)));
+                }
+            }
         }
+
+        None
+    }
+
+    // ignores the edge costs, the cost of the path is the number of edges
+    pub fn bfs(&self, start: &T, end: &T) -> Option<Path<T>> {
+        let (&start, &end) = (self.nodes.get(start)?, self.nodes.get(end)?);
+
+        let mut steps = HashMap::from([(start, 0)]);
+        let mut prev = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            let cost = steps[&node];
+            if node == end {
+                return Some(self.path(&prev, end, cost));
+            }
+
+            for edge in self.possible(node) {
+                if let Entry::Vacant(e) = steps.entry(edge.to) {
+                    e.insert(cost + 1);
+                    prev.insert(edge.to, node);
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        None
+    }
+
+    // Floyd-Warshall
+    pub fn all_pairs(&self) -> Distances {
+        let size = self.len();
+        let mut d = Distances {
+            size,
+            dist: vec![None; size * size],
+        };
+
+        for i in 0..size {
+            let from = NodeId(i);
+            d.set(from, from, 0);
+            for edge in self.possible(from) {
+                if d.get(from, edge.to).is_none_or(|c| edge.cost < c) {
+                    d.set(from, edge.to, edge.cost);
+                }
+            }
+        }
+
+        for k in (0..size).map(NodeId) {
+            for i in (0..size).map(NodeId) {
+                let Some(ik) = d.get(i, k) else { continue };
+                for j in (0..size).map(NodeId) {
+                    if let Some(kj) = d.get(k, j) {
+                        if d.get(i, j).is_none_or(|c| ik + kj < c) {
+                            d.set(i, j, ik + kj);
+                        }
+                    }
+                }
+            }
+        }
+
+        d
+    }
+
+    // a graph of just the `keep` nodes, with an edge wherever one can
+    // reach another costing the shortest distance between them
+    pub fn contract(&self, keep: &[T]) -> Graph<T> {
+        let dist = self.all_pairs();
+        let mut graph = Graph::new();
+
+        for from in keep {
+            graph.add_node(from.clone());
+            let Some(f) = self.node_id(from) else {
+                continue;
+            };
+            for to in keep.iter().filter(|&to| to != from) {
+                let cost = self.node_id(to).and_then(|t| dist.get(f, t));
+                if let Some(cost) = cost {
+                    graph.add_edge(from.clone(), to.clone(), cost);
+                }
+            }
+        }
+
+        graph
+    }
+
+    // follows `prev` back from `end` to the start
+    fn path(&self, prev: &HashMap<NodeId, NodeId>, end: NodeId, cost: usize) -> Path<T> {
+        let mut nodes = vec![self.node_data(&end).clone()];
+        let mut node = end;
+        while let Some(p) = prev.get(&node) {
+            nodes.push(self.node_data(p).clone());
+            node = *p;
+        }
+        nodes.reverse();
+
+        Path { cost, nodes }
     }
 }
 
@@ -105,39 +267,101 @@ impl<T: Debug + Hash + Eq + Clone> Graph<T> {
     }
 }
 
-struct ShortestPath<'a, T: Hash + Eq> {
-    graph: &'a Graph<T>,
-    seen: HashMap<NodeId, usize>,
-    end: NodeId,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl<'a, T: Hash + Eq + Clone> ShortestPath<'a, T> {
-    fn find(graph: &'a Graph<T>, start: &NodeId, &end: &NodeId) -> Option<usize> {
-        let seen = HashMap::new();
-        let mut sp = Self { graph, seen, end };
-        sp.find_path(start, 0)
+    // a -1- b -1- c
+    //  \          |
+    //   ---5---- d 1
+    fn graph() -> Graph<char> {
+        let mut g = Graph::new();
+        g.add_bidirectional_edge('a', 'b', 1);
+        g.add_bidirectional_edge('b', 'c', 1);
+        g.add_bidirectional_edge('c', 'd', 1);
+        g.add_bidirectional_edge('a', 'd', 5);
+        g.add_node('e');
+        g
     }
 
-    fn find_path(&mut self, &node: &NodeId, cost: usize) -> Option<usize> {
-        self.seen.insert(node, cost);
-        let mut remaining = Vec::new();
-
-        for edge in self.graph.possible(node) {
-            let cost = cost + edge.cost;
-            if edge.to == self.end {
-                return Some(cost);
-            }
+    #[test]
+    fn test_dijkstra() {
+        let g = graph();
+        assert_eq!(
+            g.dijkstra(&'a', &'d'),
+            Some(Path {
+                cost: 3,
+                nodes: vec!['a', 'b', 'c', 'd']
+            })
+        );
+        assert_eq!(g.shortest_path(&'d', &'a'), Some(3));
+        assert_eq!(g.shortest_path(&'a', &'a'), Some(0));
+        assert_eq!(g.dijkstra(&'a', &'e'), None);
+        assert_eq!(g.dijkstra(&'a', &'z'), None);
+    }
 
-            let check = match self.seen.get(&edge.to) {
-                Some(x) => cost < *x,
-                None => true,
-            };
+    #[test]
+    fn test_bfs() {
+        let g = graph();
+        assert_eq!(
+            g.bfs(&'a', &'d'),
+            Some(Path {
+                cost: 1,
+                nodes: vec!['a', 'd']
+            })
+        );
+        assert_eq!(g.bfs(&'a', &'e'), None);
+    }
 
-            if check {
-                remaining.push(self.find_path(&edge.to, cost));
+    #[test]
+    fn test_astar() {
+        let mut g = Graph::new();
+        for x in 0..10i32 {
+            for y in 0..10i32 {
+                if x < 9 {
+                    g.add_bidirectional_edge((x, y), (x + 1, y), 1);
+                }
+                if y < 9 {
+                    g.add_bidirectional_edge((x, y), (x, y + 1), 1);
+                }
             }
         }
+        let manhattan = |&(x, y): &(i32, i32)| ((9 - x) + (9 - y)) as usize;
+        let path = g.astar(&(0, 0), &(9, 9), manhattan).unwrap();
+        assert_eq!(path.cost, 18);
+        assert_eq!(path.nodes.len(), 19);
+        assert_eq!(path.nodes.first(), Some(&(0, 0)));
+        assert_eq!(path.nodes.last(), Some(&(9, 9)));
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        let g = graph();
+        let d = g.all_pairs();
+        let id = |c| g.node_id(&c).unwrap();
+        assert_eq!(d.get(id('a'), id('d')), Some(3));
+        assert_eq!(d.get(id('d'), id('b')), Some(2));
+        assert_eq!(d.get(id('c'), id('c')), Some(0));
+        assert_eq!(d.get(id('a'), id('e')), None);
+    }
+
+    #[test]
+    fn test_contract() {
+        let g = graph().contract(&['a', 'd', 'e']);
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.next(&'a'), [Edge::new(g.node_id(&'d').unwrap(), 3)]);
+        assert_eq!(g.next(&'d'), [Edge::new(g.node_id(&'a').unwrap(), 3)]);
+        assert!(g.next(&'e').is_empty());
+    }
 
-        remaining.into_iter().flatten().min()
+    #[test]
+    fn test_long_chain() {
+        // deep enough to overflow the stack when searched recursively
+        let mut g = Graph::new();
+        for i in 0..200_000 {
+            g.add_edge(i, i + 1, 2);
+        }
+        assert_eq!(g.shortest_path(&0, &200_000), Some(400_000));
+        assert_eq!(g.bfs(&0, &200_000).map(|p| p.cost), Some(200_000));
     }
 }
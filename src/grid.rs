@@ -0,0 +1,504 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::parse::ParseError;
+
+// y grows down the page, the way the puzzle maps are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point::new(0, 0);
+    pub const UP: Point = Point::new(0, -1);
+    pub const DOWN: Point = Point::new(0, 1);
+    pub const LEFT: Point = Point::new(-1, 0);
+    pub const RIGHT: Point = Point::new(1, 0);
+
+    pub const ORTHOGONAL: [Point; 4] = [Self::UP, Self::DOWN, Self::LEFT, Self::RIGHT];
+    pub const ALL: [Point; 8] = [
+        Self::UP,
+        Self::DOWN,
+        Self::LEFT,
+        Self::RIGHT,
+        Point::new(-1, -1),
+        Point::new(1, -1),
+        Point::new(-1, 1),
+        Point::new(1, 1),
+    ];
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(self, other: Point) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn neighbours4(self) -> impl Iterator<Item = Point> {
+        Self::ORTHOGONAL.into_iter().map(move |d| self + d)
+    }
+
+    pub fn neighbours8(self) -> impl Iterator<Item = Point> {
+        Self::ALL.into_iter().map(move |d| self + d)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Self::Output {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Self::Output {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+// an inclusive rectangle of points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    // the smallest rectangle holding all of `points`, None if there are none
+    pub fn of(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |b, p| {
+            Self::new(
+                Point::new(b.min.x.min(p.x), b.min.y.min(p.y)),
+                Point::new(b.max.x.max(p.x), b.max.y.max(p.y)),
+            )
+        }))
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.max.x - self.min.x + 1
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.y - self.min.y + 1
+    }
+
+    pub fn area(&self) -> i64 {
+        self.width() * self.height()
+    }
+
+    // row by row from the top left
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let Bounds { min, max } = *self;
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+    }
+}
+
+// the lines of a character map with their 1 based line numbers. only the
+// blank lines around the map are dropped, leading spaces can matter
+fn map_lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let trimmed = s.trim_start_matches(['\n', '\r']);
+    let skipped = s[..s.len() - trimmed.len()].matches('\n').count();
+    trimmed
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(move |(i, line)| (skipped + i + 1, line))
+}
+
+// the cells of a character map, `cell` returns None for characters that
+// don't belong in it
+fn map_cells<T>(
+    s: &str,
+    expected: &str,
+    mut cell: impl FnMut(char) -> Option<T>,
+) -> Result<Vec<(Point, T)>, ParseError> {
+    let mut cells = Vec::new();
+    for (y, (n, line)) in map_lines(s).enumerate() {
+        for (x, (i, c)) in line.char_indices().enumerate() {
+            match cell(c) {
+                Some(v) => cells.push((Point::new(x as i64, y as i64), v)),
+                None => {
+                    let bad = &line[i..i + c.len_utf8()];
+                    let err = ParseError::new(line, bad, format!("expected {}", expected));
+                    return Err(err.at_line(n));
+                }
+            }
+        }
+    }
+    Ok(cells)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    // every line of `s` must be the same length, `cell` returns None for
+    // characters that don't belong in the map
+    pub fn parse<F>(s: &str, expected: &str, cell: F) -> Result<Self, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+
+        for (n, line) in map_lines(s) {
+            let w = line.chars().count();
+            match width {
+                None => width = Some(w),
+                Some(width) if w < width => {
+                    let msg = format!("expected {} cells like the first row", width);
+                    return Err(ParseError::missing(line, msg).at_line(n));
+                }
+                Some(width) if w > width => {
+                    let extra = line.char_indices().nth(width).map_or(0, |(i, _)| i);
+                    let msg = format!("expected {} cells like the first row", width);
+                    return Err(ParseError::new(line, &line[extra..], msg).at_line(n));
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        let cells = map_cells(s, expected, cell)?;
+
+        Ok(Self {
+            width: width.ok_or_else(|| ParseError::missing(s, "expected a map"))?,
+            height,
+            cells: cells.into_iter().map(|(_, v)| v).collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::ORIGIN,
+            Point::new(self.width as i64 - 1, self.height as i64 - 1),
+        )
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    fn index_of(&self, p: Point) -> Option<usize> {
+        self.contains(p)
+            .then(|| p.y as usize * self.width + p.x as usize)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index_of(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index_of(p).map(|i| &mut self.cells[i])
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        self.bounds().points()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    // the first point, row by row, whose cell matches
+    pub fn find(&self, f: impl Fn(&T) -> bool) -> Option<Point> {
+        self.iter().find(|(_, v)| f(v)).map(|(p, _)| p)
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    // the neighbours of `p` that are inside the grid
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbours4().filter(|&n| self.contains(n))
+    }
+
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbours8().filter(|&n| self.contains(n))
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &Self::Output {
+        match self.get(p) {
+            Some(v) => v,
+            None => panic!("{:?} is outside the grid", p),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut Self::Output {
+        match self.get_mut(p) {
+            Some(v) => v,
+            None => panic!("{:?} is outside the grid", p),
+        }
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for v in self.row(y) {
+                write!(f, "{}", v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// for maps that are mostly empty or have no fixed size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // characters in `empty` are left out, `cell` returns None for
+    // characters that don't belong in the map
+    pub fn parse<F>(s: &str, empty: &[char], expected: &str, cell: F) -> Result<Self, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut cell = cell;
+        let keep = |c: char| {
+            if empty.contains(&c) {
+                Some(None)
+            } else {
+                cell(c).map(Some)
+            }
+        };
+        let cells = map_cells(s, expected, keep)?
+            .into_iter()
+            .filter_map(|(p, v)| Some((p, v?)))
+            .collect();
+        Ok(Self { cells })
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.cells.get(&p)
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    pub fn insert(&mut self, p: Point, v: T) -> Option<T> {
+        self.cells.insert(p, v)
+    }
+
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        self.cells.remove(&p)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(p, v)| (*p, v))
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::of(self.points())
+    }
+
+    // draws the bounding box, with `empty` where there is nothing
+    pub fn render(&self, empty: char) -> String
+    where
+        T: Display,
+    {
+        let Some(bounds) = self.bounds() else {
+            return String::new();
+        };
+        let mut s = String::new();
+        for y in bounds.min.y..=bounds.max.y {
+            if y > bounds.min.y {
+                s.push('\n');
+            }
+            for x in bounds.min.x..=bounds.max.x {
+                match self.get(Point::new(x, y)) {
+                    Some(v) => s.push_str(&v.to_string()),
+                    None => s.push(empty),
+                }
+            }
+        }
+        s
+    }
+}
+
+impl<T: Display> Display for SparseGrid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render('.'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let p = Point::new(2, 3);
+        assert_eq!(p + Point::UP * 2, Point::new(2, 1));
+        assert_eq!(p - Point::new(5, 5), Point::new(-3, -2));
+        assert_eq!(-p, Point::new(-2, -3));
+        assert_eq!(p.manhattan(Point::ORIGIN), 5);
+        assert_eq!(p.neighbours4().count(), 4);
+        assert!(p.neighbours8().all(|n| n != p && (n - p).x.abs() <= 1));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = Bounds::of([Point::new(1, 5), Point::new(-2, 3), Point::new(0, 4)]).unwrap();
+        assert_eq!(b, Bounds::new(Point::new(-2, 3), Point::new(1, 5)));
+        assert_eq!(b.area(), 12);
+        assert_eq!(b.points().count(), 12);
+        assert!(b.contains(Point::new(0, 3)));
+        assert!(!b.contains(Point::new(2, 3)));
+        assert_eq!(Bounds::of([]), None);
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse("\n123\n456\n", "a digit", |c| c.to_digit(10)).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Point::new(2, 1)], 6);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.neighbours4(Point::ORIGIN).count(), 2);
+        assert_eq!(grid.neighbours8(Point::new(1, 0)).count(), 5);
+        assert_eq!(grid.find(|&v| v == 5), Some(Point::new(1, 1)));
+        assert_eq!(grid.map(|v| v * 2).to_string(), "246\n81012");
+    }
+
+    #[test]
+    fn test_grid_errors() {
+        let err = Grid::parse("12\n3x", "a digit", |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 2: expected a digit, found \"x\""
+        );
+        let err = Grid::parse("12\n3", "a digit", |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 2: expected 2 cells like the first row, found end of line"
+        );
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let grid = SparseGrid::parse("  #\n # ", &[' '], "`#`", |c| (c == '#').then_some('#'));
+        let grid = grid.unwrap();
+        assert_eq!(grid.len(), 2);
+        assert!(grid.contains(Point::new(2, 0)));
+        assert_eq!(
+            grid.bounds(),
+            Some(Bounds::new(Point::new(1, 0), Point::new(2, 1)))
+        );
+        assert_eq!(grid.to_string(), ".#\n#.");
+    }
+}
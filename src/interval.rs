@@ -0,0 +1,190 @@
+use std::fmt::Display;
+
+// an inclusive range of integers, lo <= hi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub lo: i64,
+    pub hi: i64,
+}
+
+impl Interval {
+    pub fn new(lo: i64, hi: i64) -> Self {
+        debug_assert!(lo <= hi, "empty interval {}..={}", lo, hi);
+        Self { lo, hi }
+    }
+
+    pub fn len(&self) -> i64 {
+        self.hi - self.lo + 1
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.lo <= other.hi && other.lo <= self.hi
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        self.overlaps(other)
+            .then(|| Interval::new(self.lo.max(other.lo), self.hi.min(other.hi)))
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..={}", self.lo, self.hi)
+    }
+}
+
+// kept sorted, with touching intervals merged, so each value is in at
+// most one interval
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        // the intervals that touch the new one are merged into it
+        let start = self.intervals.partition_point(|i| i.hi < interval.lo - 1);
+        let end = self.intervals.partition_point(|i| i.lo <= interval.hi + 1);
+
+        let mut merged = interval;
+        if start < end {
+            merged.lo = merged.lo.min(self.intervals[start].lo);
+            merged.hi = merged.hi.max(self.intervals[end - 1].hi);
+        }
+        self.intervals.splice(start..end, [merged]);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // how many values are in the set
+    pub fn coverage(&self) -> i64 {
+        self.intervals.iter().map(|i| i.len()).sum()
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        let i = self.intervals.partition_point(|i| i.hi < x);
+        self.intervals.get(i).is_some_and(|i| i.contains(x))
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut set = self.clone();
+        for &i in other.iter() {
+            set.insert(i);
+        }
+        set
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let (mut a, mut b) = (0, 0);
+        while let (Some(x), Some(y)) = (self.intervals.get(a), other.intervals.get(b)) {
+            if let Some(i) = x.intersection(y) {
+                intervals.push(i);
+            }
+            // move past whichever ends first
+            if x.hi < y.hi {
+                a += 1;
+            } else {
+                b += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    // the values in self that aren't in other
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        for &i in self.iter() {
+            let mut lo = i.lo;
+            for o in other.iter().filter(|o| o.overlaps(&i)) {
+                if o.lo > lo {
+                    intervals.push(Interval::new(lo, o.lo - 1));
+                }
+                lo = o.hi + 1;
+            }
+            if lo <= i.hi {
+                intervals.push(Interval::new(lo, i.hi));
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    // the parts of `within` that aren't covered
+    pub fn gaps(&self, within: Interval) -> IntervalSet {
+        IntervalSet::from_iter([within]).difference(self)
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for i in iter {
+            set.insert(i);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|&(lo, hi)| Interval::new(lo, hi))
+            .collect()
+    }
+
+    fn list(set: &IntervalSet) -> Vec<(i64, i64)> {
+        set.iter().map(|i| (i.lo, i.hi)).collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let s = set(&[(5, 7), (1, 2), (10, 12), (3, 3), (8, 8)]);
+        assert_eq!(list(&s), [(1, 3), (5, 8), (10, 12)]);
+        assert_eq!(s.coverage(), 10);
+        assert!(s.contains(6));
+        assert!(!s.contains(4));
+        assert!(!s.contains(13));
+
+        let s = s.union(&set(&[(0, 11)]));
+        assert_eq!(list(&s), [(0, 12)]);
+    }
+
+    #[test]
+    fn test_intersection_and_difference() {
+        let a = set(&[(0, 5), (10, 15)]);
+        let b = set(&[(3, 11), (14, 20)]);
+        assert_eq!(list(&a.intersection(&b)), [(3, 5), (10, 11), (14, 15)]);
+        assert_eq!(list(&a.difference(&b)), [(0, 2), (12, 13)]);
+        assert_eq!(list(&b.difference(&a)), [(6, 9), (16, 20)]);
+        assert!(a.intersection(&set(&[(6, 9)])).is_empty());
+    }
+
+    #[test]
+    fn test_gaps() {
+        let s = set(&[(-2, 3), (5, 9), (11, 30)]);
+        assert_eq!(list(&s.gaps(Interval::new(0, 20))), [(4, 4), (10, 10)]);
+        assert!(s.gaps(Interval::new(11, 20)).is_empty());
+    }
+}
@@ -1,9 +1,11 @@
-#![feature(test)]
 #![allow(dead_code)]
 
-use std::error::Error;
-use std::fs;
+use std::{env, process};
 
+mod bench;
+mod bignum;
+mod cli;
+mod cycle;
 mod day1;
 mod day10;
 mod day11;
@@ -30,10 +32,27 @@ mod day7;
 mod day8;
 mod day9;
 mod graph;
+mod grid;
+mod interval;
+mod ocr;
+mod parse;
+mod runner;
+mod solution;
+// copied to start a new day, built with the tests so it keeps compiling
+#[cfg(test)]
+mod template;
 mod util;
+mod verify;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let input = fs::read_to_string("../day18.txt")?;
-    day18::part2(&input)?;
-    Ok(())
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = cli::parse(&args)
+        .map_err(|e| e.into())
+        .and_then(cli::execute);
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
 }
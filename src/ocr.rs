@@ -0,0 +1,153 @@
+use std::{error::Error, fmt::Display};
+
+// the letters puzzles draw with `#` are 4 pixels wide and 6 high, with a
+// blank column between them
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+static GLYPHS: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    Height(usize),
+    // the 1 based letter and the column it starts in
+    Unknown {
+        letter: usize,
+        column: usize,
+        glyph: String,
+    },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrError::Height(rows) => write!(
+                f,
+                "expected {} rows of pixels, found {}",
+                GLYPH_HEIGHT, rows
+            ),
+            OcrError::Unknown {
+                letter,
+                column,
+                glyph,
+            } => write!(
+                f,
+                "unrecognised letter {} at column {}:\n{}",
+                letter, column, glyph
+            ),
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+// the text in an image of `#` for lit pixels, anything else is dark
+pub fn read(image: &str) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = image
+        .trim_matches('\n')
+        .lines()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::Height(rows.len()));
+    }
+
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for (i, start) in (0..width).step_by(GLYPH_WIDTH + 1).enumerate() {
+        let pixels: String = rows
+            .iter()
+            .flat_map(|row| {
+                (start..start + GLYPH_WIDTH).map(|x| match row.get(x) {
+                    Some(true) => '#',
+                    _ => '.',
+                })
+            })
+            .collect();
+
+        match GLYPHS.iter().find(|(_, glyph)| *glyph == pixels) {
+            Some(&(c, _)) => text.push(c),
+            // blank space after the last letter
+            None if !pixels.contains('#') => {}
+            None => {
+                let glyph = pixels.as_bytes().chunks(GLYPH_WIDTH);
+                return Err(OcrError::Unknown {
+                    letter: i + 1,
+                    column: start + 1,
+                    glyph: glyph
+                        .map(|row| String::from_utf8_lossy(row))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                });
+            }
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // draws the text the way the puzzles do
+    fn draw(text: &str) -> String {
+        let glyphs: Vec<&str> = text
+            .chars()
+            .map(|c| GLYPHS.iter().find(|(g, _)| *g == c).unwrap().1)
+            .collect();
+        let rows: Vec<String> = (0..GLYPH_HEIGHT)
+            .map(|y| {
+                let row = y * GLYPH_WIDTH..(y + 1) * GLYPH_WIDTH;
+                let parts: Vec<&str> = glyphs.iter().map(|g| &g[row.clone()]).collect();
+                parts.join(".")
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    #[test]
+    fn test_read() {
+        let alphabet: String = GLYPHS.iter().map(|(c, _)| c).collect();
+        assert_eq!(read(&draw(&alphabet)), Ok(alphabet));
+        assert_eq!(read(&draw("FZBPBFZF")), Ok("FZBPBFZF".to_owned()));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(read("#..#\n#..#"), Err(OcrError::Height(2)));
+
+        let mut image = draw("HELLO");
+        // break the top of the second L
+        image.replace_range(15..16, ".");
+        let err = read(&image).unwrap_err();
+        assert_eq!(
+            err,
+            OcrError::Unknown {
+                letter: 4,
+                column: 16,
+                glyph: "....\n#...\n#...\n#...\n#...\n####".to_owned()
+            }
+        );
+        assert!(err
+            .to_string()
+            .starts_with("unrecognised letter 4 at column 16"));
+    }
+}
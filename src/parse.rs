@@ -0,0 +1,140 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // 1 based, not known until the error reaches whoever split the lines
+    line: Option<usize>,
+    // 1 based, in chars
+    column: usize,
+    text: String,
+    message: String,
+}
+
+// the column of `text` within `source`, or 1 if it isn't a slice of it
+fn column(source: &str, text: &str) -> usize {
+    let start = source.as_ptr() as usize;
+    let offset = (text.as_ptr() as usize).wrapping_sub(start);
+    if offset <= source.len() && source.is_char_boundary(offset) {
+        source[..offset].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+impl ParseError {
+    // `text` is the offending part of `source`
+    pub fn new<M: Into<String>>(source: &str, text: &str, message: M) -> Self {
+        Self {
+            line: None,
+            column: column(source, text),
+            text: text.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    // `source` ended before we found what we expected
+    pub fn missing<M: Into<String>>(source: &str, message: M) -> Self {
+        Self {
+            line: None,
+            column: source.chars().count() + 1,
+            text: String::new(),
+            message: message.into(),
+        }
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    // for parsers that work on a block of lines, moves the error to
+    // where the block starts in the whole input
+    pub fn offset_lines(mut self, offset: usize) -> Self {
+        if let Some(line) = self.line.as_mut() {
+            *line += offset;
+        }
+        self
+    }
+
+    // for errors from parsing `inner`, a part of the line `outer`,
+    // moves the column to where it is in `outer`
+    pub fn within(mut self, outer: &str, inner: &str) -> Self {
+        self.column += column(outer, inner) - 1;
+        self
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}, ", line)?;
+        }
+        write!(f, "column {}: {}, ", self.column, self.message)?;
+        if self.text.is_empty() && self.line.is_some() {
+            write!(f, "found end of line")
+        } else if self.text.is_empty() {
+            write!(f, "found end of input")
+        } else {
+            write!(f, "found {:?}", self.text)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+pub fn number<T: FromStr>(source: &str, text: &str) -> Result<T, ParseError> {
+    text.parse()
+        .map_err(|_| ParseError::new(source, text, "expected a number"))
+}
+
+// the `n`th whitespace separated token of `source`
+pub fn token<'a>(source: &'a str, n: usize, what: &str) -> Result<&'a str, ParseError> {
+    source
+        .split_ascii_whitespace()
+        .nth(n)
+        .ok_or_else(|| ParseError::missing(source, format!("expected {}", what)))
+}
+
+// `text` with `prefix` removed, e.g. the number in `x=12`
+pub fn after<'a>(source: &str, text: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+    text.strip_prefix(prefix)
+        .ok_or_else(|| ParseError::new(source, text, format!("expected {:?}", prefix)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = "move 1 from x to 3";
+        let err = number::<usize>(line, &line[12..13]).unwrap_err();
+        assert_eq!(err.column(), 13);
+        assert_eq!(err.text(), "x");
+        assert_eq!(
+            err.at_line(4).to_string(),
+            "line 4, column 13: expected a number, found \"x\""
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let err = token("addx", 1, "a value").unwrap_err().at_line(2);
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 5: expected a value, found end of line"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    error::Error,
+    fs, hint,
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    day1::Day1, day10::Day10, day11::Day11, day12::Day12, day13::Day13, day14::Day14, day15::Day15,
+    day16::Day16, day17::Day17, day18::Day18, day19::Day19, day2::Day2, day20::Day20, day21::Day21,
+    day22::Day22, day23::Day23, day24::Day24, day25::Day25, day3::Day3, day4::Day4, day5::Day5,
+    day6::Day6, day7::Day7, day8::Day8, day9::Day9, solution::Solution,
+};
+
+type SolveFn = fn(&str, &[u8]) -> Result<Vec<String>, Box<dyn Error>>;
+type TimeFn = fn(&str) -> Result<Vec<Duration>, Box<dyn Error>>;
+type TraceFn = fn(&str, &mut dyn Write) -> Result<(), Box<dyn Error>>;
+
+pub struct Day {
+    pub day: u8,
+    pub parts: u8,
+    solve: SolveFn,
+    time: TimeFn,
+    trace: TraceFn,
+}
+
+impl Day {
+    const fn new<S: Solution>(day: u8) -> Self {
+        Self {
+            day,
+            parts: S::PARTS,
+            solve: solve::<S>,
+            time: time::<S>,
+            trace: trace::<S>,
+        }
+    }
+
+    // parses the input once and returns the answer to each of the parts
+    pub fn solve(&self, input: &str, parts: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        (self.solve)(input, parts)
+    }
+
+    // how long parsing and then each part took, in that order
+    pub fn time(&self, input: &str) -> Result<Vec<Duration>, Box<dyn Error>> {
+        (self.time)(input)
+    }
+
+    pub fn trace(&self, input: &str, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        (self.trace)(input, out)
+    }
+}
+
+fn solve<S: Solution>(input: &str, parts: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let input = S::parse(input)?;
+    parts
+        .iter()
+        .map(|part| match part {
+            1 => Ok(S::part1(&input)?.to_string()),
+            2 => Ok(S::part2(&input)?.to_string()),
+            p => Err(format!("there is no part {}", p).into()),
+        })
+        .collect()
+}
+
+fn trace<S: Solution>(input: &str, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    S::trace(&S::parse(input)?, out)
+}
+
+fn time<S: Solution>(input: &str) -> Result<Vec<Duration>, Box<dyn Error>> {
+    let start = Instant::now();
+    let input = hint::black_box(S::parse(hint::black_box(input))?);
+    let mut times = vec![start.elapsed()];
+
+    let start = Instant::now();
+    hint::black_box(S::part1(&input)?);
+    times.push(start.elapsed());
+
+    if S::PARTS > 1 {
+        let start = Instant::now();
+        hint::black_box(S::part2(&input)?);
+        times.push(start.elapsed());
+    }
+
+    Ok(times)
+}
+
+pub static DAYS: [Day; 25] = [
+    Day::new::<Day1>(1),
+    Day::new::<Day2>(2),
+    Day::new::<Day3>(3),
+    Day::new::<Day4>(4),
+    Day::new::<Day5>(5),
+    Day::new::<Day6>(6),
+    Day::new::<Day7>(7),
+    Day::new::<Day8>(8),
+    Day::new::<Day9>(9),
+    Day::new::<Day10>(10),
+    Day::new::<Day11>(11),
+    Day::new::<Day12>(12),
+    Day::new::<Day13>(13),
+    Day::new::<Day14>(14),
+    Day::new::<Day15>(15),
+    Day::new::<Day16>(16),
+    Day::new::<Day17>(17),
+    Day::new::<Day18>(18),
+    Day::new::<Day19>(19),
+    Day::new::<Day20>(20),
+    Day::new::<Day21>(21),
+    Day::new::<Day22>(22),
+    Day::new::<Day23>(23),
+    Day::new::<Day24>(24),
+    Day::new::<Day25>(25),
+];
+
+pub fn find(day: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+// `-` reads the puzzle input from stdin
+pub fn read_input(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+// where the inputs have always lived, next to the checkout
+pub static DEFAULT_INPUT_DIR: &str = "..";
+
+pub fn input_path(dir: &str, day: u8) -> String {
+    format!("{}/day{}.txt", dir.trim_end_matches('/'), day)
+}
+
+pub fn default_input(day: u8) -> String {
+    input_path(DEFAULT_INPUT_DIR, day)
+}
@@ -0,0 +1,22 @@
+use std::{error::Error, fmt::Display, io::Write};
+
+// Every day parses its input once and then answers both parts from it.
+pub trait Solution {
+    type Input;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    // only day 25 has a single part
+    const PARTS: u8 = 2;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>>;
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>>;
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>>;
+
+    // step by step output for `run --trace`, for the days that have it
+    fn trace(_input: &Self::Input, _out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        Err("this day has no trace".into())
+    }
+}
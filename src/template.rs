@@ -1,13 +1,31 @@
 use std::error::Error;
 
-use crate::util;
+use crate::{solution::Solution, util};
 
-pub fn part1(input: &str) -> Result<(), Box<dyn Error>> {
-    Ok(())
-}
+static TEST_INPUT: &str = "";
+
+pub struct DayN;
+
+impl Solution for DayN {
+    type Input = Vec<String>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, Box<dyn Error>> {
+        // read_lines gives one empty line for empty input
+        Ok(util::read_lines(input)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .collect())
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer1, Box<dyn Error>> {
+        Ok(input.len())
+    }
 
-pub fn part2(input: &str) -> Result<(), Box<dyn Error>> {
-    Ok(())
+    fn part2(input: &Self::Input) -> Result<Self::Answer2, Box<dyn Error>> {
+        Ok(input.len())
+    }
 }
 
 #[cfg(test)]
@@ -16,6 +34,7 @@ mod tests {
 
     #[test]
     fn test() {
-        assert_eq!(1, 1);
+        let input = DayN::parse(TEST_INPUT).unwrap();
+        assert_eq!(DayN::part1(&input).unwrap(), 0);
     }
 }
@@ -1,3 +1,12 @@
+use std::{
+    panic,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::parse::ParseError;
+
 pub fn read_lines_as_ints(s: &str) -> impl Iterator<Item = i32> + '_ {
     s.trim()
         .split('\n')
@@ -7,3 +16,94 @@ pub fn read_lines_as_ints(s: &str) -> impl Iterator<Item = i32> + '_ {
 pub fn read_lines(s: &str) -> impl Iterator<Item = &str> {
     s.trim().split('\n')
 }
+
+// like `read_lines` but with the 1 based line number in the original input
+pub fn numbered_lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let skipped = s[..s.len() - s.trim_start().len()].matches('\n').count();
+    read_lines(s)
+        .enumerate()
+        .map(move |(i, line)| (skipped + i + 1, line))
+}
+
+pub fn parse_lines<T: FromStr<Err = ParseError>>(s: &str) -> Result<Vec<T>, ParseError> {
+    numbered_lines(s)
+        .map(|(n, line)| line.parse().map_err(|e: ParseError| e.at_line(n)))
+        .collect()
+}
+
+// blank line separated blocks, with the number of lines before each block
+pub fn blocks(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = s[..s.len() - s.trim_start().len()].matches('\n').count();
+    s.trim().split("\n\n").map(move |block| {
+        let start = offset;
+        offset += block.lines().count() + 1;
+        (start, block)
+    })
+}
+
+// how many threads `par_map` uses, 0 is one per core
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_threads(n: usize) {
+    THREADS.store(n, Ordering::Relaxed);
+}
+
+pub fn threads() -> usize {
+    match THREADS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+// `f` of every item, worked out on up to `threads()` threads. the threads
+// take the next item as they finish one, but the results come back in the
+// same order as the items
+pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = threads().min(items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+
+    results.sort_unstable_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_par_map() {
+        let items: Vec<u64> = (0..1000).collect();
+        let squares = par_map(&items, |x| x * x);
+        assert_eq!(squares, items.iter().map(|x| x * x).collect::<Vec<_>>());
+        assert_eq!(par_map(&[] as &[u64], |x| x + 1), []);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs};
+
+use crate::{parse::ParseError, runner, util};
+
+// expected answers keyed by (day, part), read from a small subset of TOML:
+//
+// # comments
+// [day1]
+// part1 = 70374
+// part2 = "204610"
+//
+// strings understand \n, \t, \" and \\ so multi line answers fit on one line
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Answers(BTreeMap<(u8, u8), String>);
+
+// e.g. [day12]
+fn parse_section(line: &str) -> Result<u8, ParseError> {
+    let name = line
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| ParseError::new(line, line, "expected a [dayN] section"))?
+        .trim();
+    let day = name
+        .strip_prefix("day")
+        .ok_or_else(|| ParseError::new(line, name, "expected a [dayN] section"))?;
+    match day.parse() {
+        Ok(d) if (1..=25).contains(&d) => Ok(d),
+        _ => Err(ParseError::new(line, day, "expected a day from 1 to 25")),
+    }
+}
+
+fn parse_string(line: &str, s: &str) -> Result<String, ParseError> {
+    let body = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .filter(|_| s.len() >= 2)
+        .ok_or_else(|| ParseError::new(line, s, "expected a quoted string"))?;
+
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some((_, 'n')) => out.push('\n'),
+            Some((_, 't')) => out.push('\t'),
+            Some((_, '"')) => out.push('"'),
+            Some((_, '\\')) => out.push('\\'),
+            Some((j, c)) => {
+                let escape = &body[i..j + c.len_utf8()];
+                return Err(ParseError::new(line, escape, "unsupported escape"));
+            }
+            None => return Err(ParseError::new(line, &body[i..], "unfinished escape")),
+        }
+    }
+    Ok(out)
+}
+
+// e.g. part1 = 70374
+fn parse_entry(line: &str) -> Result<(u8, String), ParseError> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| ParseError::missing(line, "expected `partN = answer`"))?;
+    let key = key.trim();
+    let part = match key {
+        "part1" => 1,
+        "part2" => 2,
+        _ => return Err(ParseError::new(line, key, "expected part1 or part2")),
+    };
+
+    let value = value.trim();
+    let answer = if value.starts_with('"') {
+        parse_string(line, value)?
+    } else if value.parse::<i64>().is_ok() {
+        value.to_owned()
+    } else if value.is_empty() {
+        return Err(ParseError::missing(line, "expected an answer"));
+    } else {
+        return Err(ParseError::new(
+            line,
+            value,
+            "expected a number or a string",
+        ));
+    };
+    Ok((part, answer))
+}
+
+impl Answers {
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        let mut answers = BTreeMap::new();
+        let mut day = None;
+
+        for (n, line) in util::numbered_lines(s) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                day = Some(parse_section(line).map_err(|e| e.at_line(n))?);
+                continue;
+            }
+
+            let d = day.ok_or_else(|| {
+                ParseError::new(line, line, "expected a [dayN] section first").at_line(n)
+            })?;
+            let (part, answer) = parse_entry(line).map_err(|e| e.at_line(n))?;
+            if answers.insert((d, part), answer).is_some() {
+                let err = ParseError::new(line, line, "duplicate answer");
+                return Err(err.at_line(n));
+            }
+        }
+
+        Ok(Self(answers))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.0.get(&(day, part)).map(|s| s.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    // the answer didn't match, or the solution returned an error
+    Fail(String),
+    // no input or no expected answer, so there is nothing to check
+    Missing(String),
+}
+
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail(_) => "FAIL",
+            Outcome::Missing(_) => "missing",
+        }
+    }
+}
+
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    pub outcome: Outcome,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "day {:>2} part {}: {}",
+            self.day,
+            self.part,
+            self.outcome.label()
+        )?;
+        match &self.outcome {
+            Outcome::Pass => Ok(()),
+            Outcome::Fail(why) | Outcome::Missing(why) => write!(f, " ({})", why),
+        }
+    }
+}
+
+fn compare(expected: Option<&str>, actual: &str) -> Outcome {
+    match expected {
+        None => Outcome::Missing("no expected answer".to_owned()),
+        Some(e) if e == actual => Outcome::Pass,
+        Some(e) => Outcome::Fail(format!("expected {:?}, got {:?}", e, actual)),
+    }
+}
+
+// checks both parts of a day against `answers`, `input` is None when
+// there is no input file for it
+pub fn check_day(day: &runner::Day, input: Option<&str>, answers: &Answers) -> Vec<Check> {
+    let parts: Vec<u8> = (1..=day.parts).collect();
+    let outcomes: Vec<Outcome> = match input {
+        None => parts
+            .iter()
+            .map(|_| Outcome::Missing("no input".to_owned()))
+            .collect(),
+        // don't bother solving when there is nothing to compare with
+        Some(_) if parts.iter().all(|&p| answers.get(day.day, p).is_none()) => parts
+            .iter()
+            .map(|&p| compare(answers.get(day.day, p), ""))
+            .collect(),
+        Some(input) => match day.solve(input, &parts) {
+            Ok(actual) => parts
+                .iter()
+                .zip(actual)
+                .map(|(&p, a)| compare(answers.get(day.day, p), &a))
+                .collect(),
+            Err(e) => parts.iter().map(|_| Outcome::Fail(e.to_string())).collect(),
+        },
+    };
+
+    parts
+        .into_iter()
+        .zip(outcomes)
+        .map(|(part, outcome)| Check {
+            day: day.day,
+            part,
+            outcome,
+        })
+        .collect()
+}
+
+// runs the days (all of them when `only` is None) against the inputs in
+// `dir`, prints a line per part and fails if any part failed
+pub fn verify(dir: &str, answers_path: &str, only: Option<u8>) -> Result<(), Box<dyn Error>> {
+    let answers =
+        fs::read_to_string(answers_path).map_err(|e| format!("{}: {}", answers_path, e))?;
+    let answers = Answers::parse(&answers).map_err(|e| format!("{}: {}", answers_path, e))?;
+
+    let mut checks = Vec::new();
+    for day in runner::DAYS
+        .iter()
+        .filter(|d| only.is_none_or(|o| o == d.day))
+    {
+        let input = fs::read_to_string(runner::input_path(dir, day.day)).ok();
+        for check in check_day(day, input.as_deref(), &answers) {
+            println!("{}", check);
+            checks.push(check);
+        }
+    }
+
+    let count = |f: fn(&Outcome) -> bool| checks.iter().filter(|c| f(&c.outcome)).count();
+    let passed = count(|o| *o == Outcome::Pass);
+    let failed = count(|o| matches!(o, Outcome::Fail(_)));
+    let missing = count(|o| matches!(o, Outcome::Missing(_)));
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        passed, failed, missing
+    );
+
+    if failed > 0 {
+        return Err(format!("{} of {} answers failed", failed, checks.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answers() {
+        let answers = Answers::parse(
+            "# checked in answers
+[day1]
+part1 = 24000
+part2 = \"45000\"
+
+[day10]
+part1 = 13140
+part2 = \"##..\\n..##\"
+",
+        )
+        .unwrap();
+        assert_eq!(answers.get(1, 1), Some("24000"));
+        assert_eq!(answers.get(1, 2), Some("45000"));
+        assert_eq!(answers.get(10, 2), Some("##..\n..##"));
+        assert_eq!(answers.get(2, 1), None);
+    }
+
+    #[test]
+    fn test_parse_answers_error() {
+        let err = Answers::parse("[day1]\npart1 = 1\npart3 = 2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: expected part1 or part2, found \"part3\""
+        );
+        let err = Answers::parse("part1 = 1").unwrap_err();
+        assert_eq!(err.line(), Some(1));
+        let err = Answers::parse("[day26]").unwrap_err();
+        assert_eq!(err.text(), "26");
+    }
+
+    #[test]
+    fn test_check_day() {
+        let day = runner::find(1).unwrap();
+        let answers = Answers::parse("[day1]\npart1 = 24000\npart2 = 1").unwrap();
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+
+        let checks = check_day(day, Some(input), &answers);
+        assert_eq!(checks[0].outcome, Outcome::Pass);
+        assert_eq!(
+            checks[1].outcome,
+            Outcome::Fail("expected \"1\", got \"45000\"".to_owned())
+        );
+
+        let checks = check_day(day, None, &answers);
+        assert!(matches!(checks[0].outcome, Outcome::Missing(_)));
+    }
+}